@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use crate::fail::WResult;
+use crate::files::{File, Files};
+use crate::keybind::{BasketAction, Bindings, KeyBinds};
+use crate::listview::ListView;
+use crate::pathlist::{FilePick, Outcome, PathList, PathListView};
+
+// Files collected from any directory or tab. While it's not empty commands
+// get them as "$s" instead of the selection, and quick actions run on them.
+lazy_static! {
+    static ref BASKET: RwLock<Vec<PathBuf>> = RwLock::new(vec![]);
+}
+
+// Adds files not in the basket yet, returns how many were added
+pub fn add(files: &[File]) -> WResult<usize> {
+    let mut basket = BASKET.write()?;
+    let len = basket.len();
+
+    for file in files {
+        if !basket.contains(&file.path) {
+            basket.push(file.path.clone());
+        }
+    }
+
+    Ok(basket.len() - len)
+}
+
+pub fn remove(file: &File) -> WResult<()> {
+    BASKET.write()?.retain(|path| path != &file.path);
+    Ok(())
+}
+
+pub fn contains(file: &File) -> bool {
+    BASKET
+        .read()
+        .map(|basket| basket.contains(&file.path))
+        .unwrap_or(false)
+}
+
+pub fn clear() -> WResult<()> {
+    BASKET.write()?.clear();
+    Ok(())
+}
+
+pub fn len() -> usize {
+    BASKET.read().map(|basket| basket.len()).unwrap_or(0)
+}
+
+pub fn is_empty() -> bool {
+    len() == 0
+}
+
+pub fn paths() -> WResult<Vec<PathBuf>> {
+    Ok(BASKET.read()?.clone())
+}
+
+pub fn files() -> WResult<Vec<File>> {
+    BASKET
+        .read()?
+        .iter()
+        .map(|path| File::new_from_path(path))
+        .collect()
+}
+
+pub struct Basket;
+
+pub type BasketView = PathListView<Basket>;
+
+impl PathList for Basket {
+    type Action = BasketAction;
+    type Pick = FilePick;
+
+    fn files(&self) -> WResult<Vec<File>> {
+        Ok(files()?
+            .into_iter()
+            .map(|mut file| {
+                file.name = file.short_string();
+                file
+            })
+            .collect())
+    }
+
+    fn header(&self, len: usize) -> String {
+        format!("Basket: {} files", len)
+    }
+
+    fn bindings(keybinds: KeyBinds) -> Bindings<BasketAction> {
+        keybinds.basket
+    }
+
+    fn goto_action() -> BasketAction {
+        BasketAction::Goto
+    }
+
+    fn do_action(&mut self,
+                 action: &BasketAction,
+                 list: &ListView<Files>) -> WResult<Outcome<FilePick>> {
+        use BasketAction::*;
+
+        let outcome = match action {
+            Close => Outcome::Close,
+            Goto => Outcome::Pick(FilePick::Goto(list.selected_file().clone())),
+            Remove => {
+                remove(list.selected_file())?;
+                Outcome::Reload
+            }
+            Clear => {
+                clear()?;
+                Outcome::Close
+            }
+            QuickActions => Outcome::Pick(FilePick::QuickActions(files()?)),
+            ExecCmd => Outcome::Pick(FilePick::ExecCmd(files()?)),
+        };
+
+        Ok(outcome)
+    }
+}
@@ -1,34 +1,147 @@
 use termion::event::Key;
 
-use std::collections::HashMap;
+use std::path::Path;
 
 use crate::fail::{WResult, WError, ErrorLog};
 use crate::widget::{Widget, WidgetCore};
 use crate::coordinates::Coordinates;
+use crate::paths::{escape_value, unescape_value};
 use crate::term;
 
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Bookmark {
+    pub key: String,
+    pub path: String,
+    pub name: Option<String>,
+    pub group: Option<String>,
+}
+
+impl Bookmark {
+    pub fn new(key: &str, path: &str) -> Bookmark {
+        Bookmark {
+            key: key.to_string(),
+            path: path.to_string(),
+            name: None,
+            group: None
+        }
+    }
+
+    // What AddBookmark asks for: "key [name] [@group]"
+    pub fn from_input(input: &str, path: &str) -> Option<Bookmark> {
+        let mut words = input.split_whitespace().collect::<Vec<&str>>();
+        let key = words.first()?.to_string();
+
+        let group = match words.last().copied().and_then(|last| last.strip_prefix('@')) {
+            Some(group) if words.len() > 1 && !group.is_empty() => {
+                words.pop();
+                Some(group.to_string())
+            }
+            _ => None
+        };
+
+        let name = match words[1..].join(" ") {
+            name if name.is_empty() => None,
+            name => Some(name)
+        };
+
+        Some(Bookmark {
+            key,
+            path: path.to_string(),
+            name,
+            group
+        })
+    }
+
+    // Lines look like "key:path", optionally followed by a tab
+    // separated name and group. That's also what ranger uses. Tabs and
+    // line breaks in the fields are escaped.
+    fn parse(line: &str) -> Option<Bookmark> {
+        let mut parts = line.splitn(2, ':');
+        let key = parts.next()?;
+        let mut fields = parts.next()?.split('\t').map(unescape_value);
+        let path = fields.next()?;
+
+        if key.is_empty() || path.is_empty() {
+            return None;
+        }
+
+        let mut field = || fields.next().filter(|field| !field.is_empty());
+
+        Some(Bookmark {
+            key: key.to_string(),
+            path,
+            name: field(),
+            group: field()
+        })
+    }
+
+    fn to_line(&self) -> String {
+        let path = escape_value(&self.path);
+
+        match (&self.name, &self.group) {
+            (None, None) => format!("{}:{}\n", self.key, path),
+            (name, group) => format!("{}:{}\t{}\t{}\n",
+                                     self.key,
+                                     path,
+                                     escape_value(name.as_deref().unwrap_or("")),
+                                     escape_value(group.as_deref().unwrap_or("")))
+        }
+    }
+
+    pub fn exists(&self) -> bool {
+        Path::new(&self.path).exists()
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+
+        [Some(&self.key), self.name.as_ref(), self.group.as_ref(), Some(&self.path)]
+            .iter()
+            .filter_map(|field| field.as_ref())
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Bookmarks {
-    mapping: HashMap<char, String>,
+    // Sorted by group, then by key
+    bookmarks: Vec<Bookmark>,
 }
 
 impl Bookmarks {
     pub fn new() -> Bookmarks {
-        let mut bm = Bookmarks { mapping: HashMap::new() };
+        let mut bm = Bookmarks { bookmarks: vec![] };
         bm.load().or_else(|_| WError::log("Couldn't load bookmarks!")).ok();
         bm
     }
-    pub fn add(&mut self, key: char, path: &str) -> WResult<()> {
-        self.mapping.insert(key, path.to_string());
+    pub fn add(&mut self, bookmark: Bookmark) -> WResult<()> {
+        self.bookmarks.retain(|bm| bm.key != bookmark.key);
+        self.bookmarks.push(bookmark);
+        self.sort();
         self.save()?;
         Ok(())
     }
-    pub fn get(&self, key: char) -> WResult<&String> {
-        if let Some(path) = self.mapping.get(&key) {
-        Ok(path)
-        } else {
-            Err(WError::BookmarkNotFound)
+    pub fn remove(&mut self, key: &str) -> WResult<()> {
+        let len = self.bookmarks.len();
+        self.bookmarks.retain(|bm| bm.key != key);
+
+        if self.bookmarks.len() == len {
+            return Err(WError::BookmarkNotFound);
         }
+
+        self.save()
+    }
+    pub fn get(&self, key: &str) -> WResult<&Bookmark> {
+        self.bookmarks
+            .iter()
+            .find(|bm| bm.key == key)
+            .ok_or(WError::BookmarkNotFound)
+    }
+    pub fn iter(&self) -> impl Iterator<Item=&Bookmark> {
+        self.bookmarks.iter()
+    }
+    fn sort(&mut self) {
+        self.bookmarks.sort_by(|a, b| (&a.group, &a.key).cmp(&(&b.group, &b.key)));
     }
     pub fn load(&mut self) -> WResult<()> {
         let bm_file = crate::paths::bookmark_path()?;
@@ -38,19 +151,10 @@ impl Bookmarks {
         }
 
         let bm_content = std::fs::read_to_string(bm_file)?;
-        let mapping = bm_content.lines()
-            .fold(HashMap::new(), |mut bm, line| {
-            let parts = line.splitn(2, ":").collect::<Vec<&str>>();
-            if parts.len() == 2 {
-                if let Some(key) = parts[0].chars().next() {
-                    let path = parts[1].to_string();
-                    bm.insert(key, path);
-                }
-            }
-            bm
-        });
-
-        self.mapping = mapping;
+        self.bookmarks = bm_content.lines()
+                                   .filter_map(Bookmark::parse)
+                                   .collect();
+        self.sort();
         Ok(())
     }
     pub fn import(&self) -> WResult<()> {
@@ -65,13 +169,11 @@ impl Bookmarks {
     }
     pub fn save(&self) -> WResult<()> {
         let bm_file = crate::paths::bookmark_path()?;
-        let bookmarks = self.mapping.iter().map(|(key, path)| {
-            format!("{}:{}\n", key, path)
-        }).collect::<String>();
-
-        std::fs::write(bm_file, bookmarks)?;
+        let bookmarks = self.bookmarks.iter()
+                                      .map(|bm| bm.to_line())
+                                      .collect::<String>();
 
-        Ok(())
+        crate::paths::write_atomic(&bm_file, bookmarks.as_bytes())
     }
 }
 
@@ -80,7 +182,11 @@ pub struct BMPopup {
     core: WidgetCore,
     bookmarks: Bookmarks,
     bookmark_path: Option<String>,
-    add_mode: bool,
+    // Keys typed so far, for keys longer than one char
+    input: String,
+    // Typing filters by key, name, group and path after "/"
+    filter: Option<String>,
+    selection: usize,
 }
 
 impl BMPopup {
@@ -89,7 +195,9 @@ impl BMPopup {
             core: core.clone(),
             bookmarks: Bookmarks::new(),
             bookmark_path: None,
-            add_mode: false
+            input: String::new(),
+            filter: None,
+            selection: 0
         };
         bmpopup.set_coordinates(&core.coordinates).log();
         bmpopup
@@ -97,6 +205,9 @@ impl BMPopup {
 
     pub fn pick(&mut self, cwd: String) -> WResult<String> {
         self.bookmark_path = Some(cwd);
+        self.input.clear();
+        self.filter = None;
+        self.selection = 0;
         self.refresh()?;
         match self.popup() {
             Ok(_) => {},
@@ -109,31 +220,138 @@ impl BMPopup {
         bookmark.ok_or(WError::BookmarkNotFound)
     }
 
-    pub fn add(&mut self, path: &str) -> WResult<()> {
-        self.add_mode = true;
-        self.bookmark_path = Some(path.to_string());
-        self.refresh()?;
-        self.get_core()?.clear()?;
-        self.popup()?;
-        self.get_core()?.clear()?;
-        Ok(())
+    pub fn add(&mut self, bookmark: Bookmark) -> WResult<()> {
+        self.bookmarks.add(bookmark)
+    }
+
+    pub fn remove(&mut self, key: &str) -> WResult<()> {
+        self.bookmarks.remove(key)
+    }
+
+    pub fn path(&self, key: &str) -> WResult<String> {
+        Ok(self.bookmarks.get(key)?.path.clone())
     }
 
     fn resize(&mut self) -> WResult<()> {
         WError::terminal_resized()?
     }
 
-    pub fn render_line(&self, n: u16, key: &char, path: &str) -> String {
-        let xsize = term::xsize();
-        let padding = xsize - 4;
+    fn visible(&self) -> Vec<&Bookmark> {
+        self.bookmarks.bookmarks.iter().filter(|bm| {
+            match &self.filter {
+                Some(filter) => bm.matches(filter),
+                None => bm.key.starts_with(&self.input)
+            }
+        }).collect()
+    }
+
+    fn groups(&self) -> usize {
+        let mut groups = self.bookmarks.bookmarks.iter()
+                                                 .filter_map(|bm| bm.group.as_ref())
+                                                 .collect::<Vec<_>>();
+        groups.dedup();
+        groups.len()
+    }
+
+    fn goto(&mut self, path: String) -> WResult<()> {
+        self.bookmark_path.replace(path);
+        WError::popup_finished()
+    }
+
+    // A bookmark with exactly the typed key comes first, even if it's not
+    // the first one shown
+    fn select_typed(&mut self) {
+        self.selection = self.visible()
+                             .iter()
+                             .position(|bm| self.filter.is_none() && bm.key == self.input)
+                             .unwrap_or(0);
+    }
+
+    fn goto_selected(&mut self) -> WResult<()> {
+        let path = match self.visible().get(self.selection) {
+            Some(bookmark) => bookmark.path.clone(),
+            None => return Ok(())
+        };
+        self.goto(path)
+    }
+
+    fn insert_key(&mut self, key: char) -> WResult<()> {
+        let mut input = self.input.clone();
+        input.push(key);
+
+        let matching = self.bookmarks.bookmarks.iter()
+                                               .filter(|bm| bm.key.starts_with(&input))
+                                               .count();
+
+        match (matching, self.bookmarks.get(&input)) {
+            (0, _) => self.core.show_status(&format!("No bookmark for: {}", input)),
+            (1, Ok(bookmark)) => {
+                let path = bookmark.path.clone();
+                self.goto(path)
+            }
+            // Wait for more keys, or Enter if the key is taken too
+            _ => {
+                self.input = input;
+                self.select_typed();
+                Ok(())
+            }
+        }
+    }
+
+    fn delete(&mut self, key: &str) -> WResult<()> {
+        self.bookmarks.remove(key)?;
+        WError::widget_resized()
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        let len = self.visible().len();
+        self.selection = match down {
+            true => (self.selection + 1).min(len.saturating_sub(1)),
+            false => self.selection.saturating_sub(1)
+        };
+    }
+
+    fn render_line(&self,
+                   n: u16,
+                   bookmark: &Bookmark,
+                   key_width: usize,
+                   selected: bool) -> String {
+        let xsize = term::xsize_u();
+        let name = bookmark.name.as_deref().unwrap_or("");
+
+        let missing = match bookmark.exists() {
+            true => String::new(),
+            false => format!(" {}(missing)", term::color_red())
+        };
+
+        let line = format!(
+            "{}{:key_width$} {}{} {}{}",
+            match selected {
+                true => term::invert(),
+                false => String::new()
+            },
+            bookmark.key,
+            term::highlight_color(),
+            name,
+            term::normal_color(),
+            bookmark.path,
+            key_width = key_width);
+
+        format!("{}{}{}{}",
+                term::goto_xy(1, n),
+                term::reset(),
+                term::sized_string_u(&(line + &missing), xsize.saturating_sub(1)),
+                term::reset())
+    }
+
+    fn render_text(&self, n: u16, text: &str) -> String {
+        let xsize = term::xsize_u();
 
         format!(
-            "{}{}{}: {:padding$}",
-            crate::term::goto_xy(1, n),
-            crate::term::reset(),
-            key,
-            path,
-            padding = padding as usize)
+            "{}{}{}",
+            term::goto_xy(1, n),
+            term::reset(),
+            term::sized_string_u(text, xsize.saturating_sub(1)))
     }
 }
 
@@ -155,7 +373,9 @@ impl Widget for BMPopup {
 
     fn set_coordinates(&mut self, _: &Coordinates) -> WResult<()> {
         let (xsize, ysize) = crate::term::size()?;
-        let len = self.bookmarks.mapping.len();
+        // Filtering only empties lines, so this stays the same while picking
+        let len = (self.bookmarks.bookmarks.len() + self.groups())
+            .min(ysize.saturating_sub(3));
         let ysize = ysize.saturating_sub( len + 1 );
 
         self.core.coordinates.set_size_u(xsize.saturating_sub(1), len);
@@ -166,20 +386,52 @@ impl Widget for BMPopup {
 
     fn get_drawlist(&self) -> WResult<String> {
         let ypos = self.get_coordinates()?.ypos();
+        let height = self.get_coordinates()?.ysize_u();
 
-        let mut drawlist = String::new();
+        let mut drawlist = match (&self.filter, self.input.as_str()) {
+            (Some(filter), _) => self.render_text(ypos, &format!("/{}", filter)),
+            (None, "") => {
+                let cwd = self.bookmark_path.as_ref().ok_or(WError::BookmarkPathNotFound)?;
+                self.render_text(ypos, &format!("`: {}", cwd))
+            }
+            (None, input) => self.render_text(ypos, &format!("> {}", input))
+        };
+
+        let visible = self.visible();
+        let key_width = visible.iter()
+                               .map(|bm| term::string_len(&bm.key))
+                               .max()
+                               .unwrap_or(1);
 
-        if !self.add_mode {
-            let cwd = self.bookmark_path.as_ref().ok_or(WError::BookmarkPathNotFound)?;
-            drawlist += &self.render_line(ypos, &'`', cwd);
+        // Group names are shown as a line on top of their bookmarks
+        let mut lines = vec![];
+        let mut group = None;
+        for (i, bookmark) in visible.iter().enumerate() {
+            if bookmark.group.is_some() && bookmark.group != group {
+                let name = bookmark.group.as_deref().unwrap_or("");
+                lines.push((None, format!("{}[{}]", term::color_yellow(), name)));
+            }
+            group = bookmark.group.clone();
+            lines.push((Some(i), String::new()));
         }
 
-        let bm_list = self.bookmarks.mapping.iter().enumerate().map(|(i, (key, path))| {
-            let line = i as u16 + ypos + 1;
-            self.render_line(line, key, path)
-        }).collect::<String>();
+        let selected_line = lines.iter()
+                                 .position(|(i, _)| *i == Some(self.selection))
+                                 .unwrap_or(0);
+        let offset = (selected_line + 1).saturating_sub(height);
+
+        for n in 0..height {
+            let line = n as u16 + ypos + 1;
 
-        drawlist += &bm_list;
+            drawlist += &match lines.get(n + offset) {
+                Some((Some(i), _)) => self.render_line(line,
+                                                       visible[*i],
+                                                       key_width,
+                                                       *i == self.selection),
+                Some((None, text)) => self.render_text(line, text),
+                None => self.render_text(line, "")
+            };
+        }
 
         Ok(drawlist)
     }
@@ -189,27 +441,76 @@ impl Widget for BMPopup {
                 self.bookmark_path = None;
                 return WError::popup_finished()
             },
-            Key::Char('`') => return WError::popup_finished(),
-            Key::Char(key) => {
-                if self.add_mode {
-                    let path = self.bookmark_path.take().ok_or(WError::BookmarkPathNotFound)?;
-                    self.bookmarks.add(key, &path)?;
-                    self.add_mode = false;
-                    self.bookmarks.save().log();
-                    return WError::popup_finished();
+            Key::Char('\n') => return self.goto_selected(),
+            Key::Up | Key::Ctrl('p') => self.move_selection(false),
+            Key::Down | Key::Ctrl('n') => self.move_selection(true),
+            Key::Delete => {
+                let key = match self.visible().get(self.selection) {
+                    Some(bookmark) => bookmark.key.clone(),
+                    None => return Ok(())
+                };
+                return self.delete(&key);
+            }
+            Key::Backspace => {
+                match self.filter.as_mut() {
+                    Some(filter) if filter.is_empty() => self.filter = None,
+                    Some(filter) => { filter.pop(); }
+                    None => { self.input.pop(); }
                 }
-                if let Ok(path) = self.bookmarks.get(key) {
-                    self.bookmark_path.replace(path.clone());
-                    return WError::popup_finished();
+                self.select_typed();
+            }
+            Key::Char(key) if self.filter.is_some() => {
+                if let Some(filter) = self.filter.as_mut() {
+                    filter.push(key);
                 }
+                self.selection = 0;
+            }
+            Key::Char('`') if self.input.is_empty() => return WError::popup_finished(),
+            Key::Char('/') if self.input.is_empty() => {
+                self.filter = Some(String::new());
+                self.selection = 0;
             }
+            Key::Char(key) => return self.insert_key(key),
             Key::Alt(key) => {
-                self.bookmarks.mapping.remove(&key);
-                self.bookmarks.save().log();
-                return WError::widget_resized();
+                let key = format!("{}{}", self.input, key);
+                return self.delete(&key);
             }
             _ => {}
         }
         Ok(())
     }
 }
+
+#[test]
+fn test_bookmark_line() {
+    let roundtrip = |bookmark: &Bookmark| Bookmark::parse(bookmark.to_line().trim_end_matches('\n'));
+
+    let mut bookmark = Bookmark::new("ab", "/tmp/with\ttab");
+    assert_eq!(bookmark.to_line(), "ab:/tmp/with\\ttab\n");
+    assert_eq!(roundtrip(&bookmark), Some(bookmark.clone()));
+
+    bookmark.group = Some(String::from("work"));
+    assert_eq!(bookmark.to_line(), "ab:/tmp/with\\ttab\t\twork\n");
+    assert_eq!(roundtrip(&bookmark), Some(bookmark.clone()));
+
+    bookmark.name = Some(String::from("new\nline"));
+    assert_eq!(roundtrip(&bookmark), Some(bookmark));
+
+    // Plain ranger bookmarks
+    assert_eq!(Bookmark::parse("a:/home/a:b"), Some(Bookmark::new("a", "/home/a:b")));
+    assert_eq!(Bookmark::parse("a:"), None);
+    assert_eq!(Bookmark::parse("/no/key"), None);
+}
+
+#[test]
+fn test_bookmark_input() {
+    let bookmark = Bookmark::from_input("pr  my projects @work", "/p").unwrap();
+    assert_eq!(bookmark.key, "pr");
+    assert_eq!(bookmark.name.as_deref(), Some("my projects"));
+    assert_eq!(bookmark.group.as_deref(), Some("work"));
+
+    assert_eq!(Bookmark::from_input("x", "/p"), Some(Bookmark::new("x", "/p")));
+    let bookmark = Bookmark::from_input("x @", "/p").unwrap();
+    assert_eq!((bookmark.name.as_deref(), bookmark.group), (Some("@"), None));
+    assert_eq!(Bookmark::from_input(" ", "/p"), None);
+}
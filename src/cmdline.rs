@@ -0,0 +1,248 @@
+use std::path::{Path, PathBuf};
+
+use crate::fail::{WError, WResult};
+use crate::files::SortBy;
+
+// Shown by the completion menu of the command prompt
+pub const COMMANDS: [(&str, &str); 7] = [
+    ("cd", "[PATH|-], go to a directory"),
+    ("sort", "name|size|mtime [reverse]"),
+    ("filter", "[PATTERN], only show matching files"),
+    ("select", "glob PATTERN|all|none|invert"),
+    ("tab", "new [PATH]|close|next|prev|N|rename [NAME]|dup|pin"),
+    ("bookmark", "add KEY [NAME]|del KEY|KEY"),
+    ("set", "OPTION VALUE, until wandex quits"),
+];
+
+pub const SUBCOMMANDS: [(&str, &[&str]); 4] = [
+    ("sort", &["name", "size", "mtime", "reverse"]),
+    ("select", &["glob", "all", "none", "invert"]),
+    ("tab", &["new", "close", "next", "prev", "rename", "dup", "pin"]),
+    ("bookmark", &["add", "del"]),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Selection {
+    Glob(String),
+    All,
+    Nothing,
+    Invert,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TabCommand {
+    New(Option<String>),
+    Close,
+    Next,
+    Prev,
+    // Counted from 1, like the tab numbers in ExecCmd
+    Goto(usize),
+    Rename(Option<String>),
+    Duplicate,
+    Pin,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BookmarkCommand {
+    Add(String, Option<String>),
+    Delete(String),
+    Goto(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExCommand {
+    Cd(Option<String>),
+    Sort(SortBy, bool),
+    Filter(Option<String>),
+    Select(Selection),
+    Tab(TabCommand),
+    Bookmark(BookmarkCommand),
+    Set(String, String),
+    // Anything from the keys file, by name
+    Action(String),
+}
+
+fn error<T>(msg: String) -> WResult<T> {
+    Err(WError::Log(msg))
+}
+
+// Splits like a shell would, minus all the expansions
+pub fn split_words(line: &str) -> WResult<Vec<String>> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut escaped = false;
+
+    for ch in line.chars() {
+        if escaped {
+            word.get_or_insert_with(String::new).push(ch);
+            escaped = false;
+            continue;
+        }
+
+        match (quote, ch) {
+            (Some(q), ch) if ch == q => quote = None,
+            (Some(_), ch) => word.get_or_insert_with(String::new).push(ch),
+            (None, '\\') => escaped = true,
+            (None, '\'') | (None, '"') => {
+                word.get_or_insert_with(String::new);
+                quote = Some(ch);
+            }
+            (None, ch) if ch.is_whitespace() => words.extend(word.take()),
+            (None, ch) => word.get_or_insert_with(String::new).push(ch),
+        }
+    }
+
+    if quote.is_some() {
+        return error(String::from("Unclosed quote in command"));
+    }
+
+    words.extend(word);
+    Ok(words)
+}
+
+// Relative paths start from the directory the command was run in
+pub fn expand_path(cwd: &Path, path: &str) -> PathBuf {
+    let home = crate::paths::home_path();
+
+    match (path, home) {
+        ("~", Ok(home)) => home,
+        (path, Ok(home)) if path.starts_with("~/") => home.join(&path[2..]),
+        (path, _) => cwd.join(path),
+    }
+}
+
+fn parse_sort(args: &[String]) -> WResult<ExCommand> {
+    let mut sort = None;
+    let mut reverse = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "name" => sort = Some(SortBy::Name),
+            "size" => sort = Some(SortBy::Size),
+            "mtime" | "time" => sort = Some(SortBy::MTime),
+            "reverse" | "rev" => reverse = true,
+            arg => return error(format!("Can't sort by {}", arg)),
+        }
+    }
+
+    match sort {
+        Some(sort) => Ok(ExCommand::Sort(sort, reverse)),
+        None => error(String::from("sort: name, size or mtime is missing")),
+    }
+}
+
+fn parse_select(args: &[String]) -> WResult<ExCommand> {
+    let selection = match args {
+        [glob, pattern] if glob == "glob" => Selection::Glob(pattern.clone()),
+        [all] if all == "all" => Selection::All,
+        [none] if none == "none" => Selection::Nothing,
+        [invert] if invert == "invert" => Selection::Invert,
+        _ => return error(String::from("Usage: select glob PATTERN|all|none|invert")),
+    };
+
+    Ok(ExCommand::Select(selection))
+}
+
+fn parse_tab(args: &[String]) -> WResult<ExCommand> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let tab = match args.as_slice() {
+        ["new"] => TabCommand::New(None),
+        ["new", path] => TabCommand::New(Some(path.to_string())),
+        ["close"] => TabCommand::Close,
+        ["next"] => TabCommand::Next,
+        ["prev"] => TabCommand::Prev,
+        ["rename"] => TabCommand::Rename(None),
+        ["rename", name @ ..] => TabCommand::Rename(Some(name.join(" "))),
+        ["dup"] => TabCommand::Duplicate,
+        ["pin"] => TabCommand::Pin,
+        [n] if n.parse::<usize>().is_ok() => TabCommand::Goto(n.parse().unwrap()),
+        _ => return error(String::from("Usage: tab new [PATH]|close|next|prev|N|rename [NAME]|dup|pin")),
+    };
+
+    Ok(ExCommand::Tab(tab))
+}
+
+fn parse_bookmark(args: &[String]) -> WResult<ExCommand> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    let bookmark = match args.as_slice() {
+        ["add", key] => BookmarkCommand::Add(key.to_string(), None),
+        ["add", key, name @ ..] => BookmarkCommand::Add(key.to_string(), Some(name.join(" "))),
+        ["del", key] => BookmarkCommand::Delete(key.to_string()),
+        [key] => BookmarkCommand::Goto(key.to_string()),
+        _ => return error(String::from("Usage: bookmark add KEY [NAME]|del KEY|KEY")),
+    };
+
+    if let BookmarkCommand::Add(key, _) = &bookmark {
+        if key.contains(':') {
+            return error(String::from("Bookmark keys can't contain \":\"!"));
+        }
+    }
+
+    Ok(ExCommand::Bookmark(bookmark))
+}
+
+pub fn parse(line: &str) -> WResult<ExCommand> {
+    let words = split_words(line)?;
+    let (name, args) = match words.split_first() {
+        Some((name, args)) => (name.as_str(), args),
+        None => return error(String::from("No command given")),
+    };
+
+    match (name, args) {
+        ("cd", []) => Ok(ExCommand::Cd(None)),
+        ("cd", [path]) => Ok(ExCommand::Cd(Some(path.clone()))),
+        ("sort", args) => parse_sort(args),
+        ("filter", []) => Ok(ExCommand::Filter(None)),
+        // Spaces in the pattern don't need quotes
+        ("filter", pattern) => Ok(ExCommand::Filter(Some(pattern.join(" ")))),
+        ("select", args) => parse_select(args),
+        ("tab", args) => parse_tab(args),
+        ("bookmark", args) => parse_bookmark(args),
+        ("set", [option]) if option.contains('=') => {
+            let (option, value) = option.split_at(option.find('=').unwrap());
+            Ok(ExCommand::Set(option.to_string(), value[1..].to_string()))
+        }
+        ("set", [option, value @ ..]) if !value.is_empty() => {
+            Ok(ExCommand::Set(option.clone(), value.join(" ")))
+        }
+        ("set", _) => error(String::from("Usage: set OPTION VALUE")),
+        (name, []) if !COMMANDS.iter().any(|(command, _)| *command == name) => {
+            Ok(ExCommand::Action(name.to_string()))
+        }
+        (name, _) => error(format!("Wrong arguments for {}", name)),
+    }
+}
+
+#[test]
+fn test_split_words() {
+    assert_eq!(split_words("tab  rename 'my tab' a\\ b \"\"").unwrap(),
+               vec!["tab", "rename", "my tab", "a b", ""]);
+    assert!(split_words("cd 'unclosed").is_err());
+}
+
+#[test]
+fn test_parse() {
+    use ExCommand::*;
+
+    assert_eq!(parse("cd").unwrap(), Cd(None));
+    assert_eq!(parse("cd '/tmp/some dir'").unwrap(), Cd(Some(String::from("/tmp/some dir"))));
+    assert_eq!(parse("sort mtime rev").unwrap(), Sort(SortBy::MTime, true));
+    assert_eq!(parse("filter a b").unwrap(), Filter(Some(String::from("a b"))));
+    assert_eq!(parse("select glob *.rs").unwrap(), Select(Selection::Glob(String::from("*.rs"))));
+    assert_eq!(parse("tab 3").unwrap(), Tab(TabCommand::Goto(3)));
+    assert_eq!(parse("tab rename my tab").unwrap(),
+               Tab(TabCommand::Rename(Some(String::from("my tab")))));
+    assert_eq!(parse("bookmark add w work stuff").unwrap(),
+               Bookmark(BookmarkCommand::Add(String::from("w"), Some(String::from("work stuff")))));
+    assert_eq!(parse("set ratios=1,2").unwrap(), Set(String::from("ratios"), String::from("1,2")));
+    assert_eq!(parse("set ratios 1 2").unwrap(), Set(String::from("ratios"), String::from("1 2")));
+    assert_eq!(parse("ToggleHidden").unwrap(), Action(String::from("ToggleHidden")));
+
+    assert!(parse("").is_err());
+    assert!(parse("sort color").is_err());
+    assert!(parse("cd a b").is_err());
+    assert!(parse("tab").is_err());
+    assert!(parse("bookmark add a:b").is_err());
+    assert!(parse("set ratios").is_err());
+}
@@ -0,0 +1,553 @@
+use std::path::PathBuf;
+
+use crate::term;
+
+// The menu never gets taller than this, it scrolls instead
+const MAX_ROWS: usize = 10;
+
+// Characters that need a backslash outside of quotes
+const SPECIAL_CHARS: &str = " \t'\"\\$&;|()<>*?[]{}#`!~";
+
+// Shell operators after which a new command starts
+const OPERATORS: [&str; 5] = ["|", "||", "&&", ";", "&"];
+
+// Substitutions done by ExecCmd, see README
+fn patterns() -> Vec<(String, String)> {
+    let tabs = (0..10).flat_map(|n| {
+        vec![(format!("${}", n), format!("directory of tab {}", n)),
+             (format!("${}s", n), format!("selected files in tab {}", n))]
+    });
+
+    std::iter::once((String::from("$s"), String::from("selected files, or the basket")))
+        .chain(std::iter::once((String::from("$b"), String::from("files in the basket"))))
+        .chain(tabs)
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    // Inserted in place of the word
+    pub value: String,
+    pub label: String,
+    pub info: Option<String>,
+    // Substitution patterns and variables are inserted as they are
+    quote: bool,
+}
+
+impl Candidate {
+    fn new(value: String, label: String, info: Option<String>) -> Candidate {
+        Candidate { value, label, info, quote: true }
+    }
+
+    fn verbatim(value: String, info: Option<String>) -> Candidate {
+        Candidate { label: value.clone(), value, info, quote: false }
+    }
+}
+
+// The word the cursor is in, with quotes and escapes already removed
+#[derive(Debug, PartialEq)]
+struct Word {
+    start: usize,
+    text: String,
+    quote: Option<char>,
+    command: bool,
+    // Everything typed before it
+    before: Vec<String>,
+}
+
+impl Word {
+    fn parse(line: &str) -> Word {
+        let mut words = vec![];
+        let mut word = Word {
+            start: 0,
+            text: String::new(),
+            quote: None,
+            command: true,
+            before: vec![],
+        };
+        let mut quote = None;
+        let mut escaped = false;
+        // The last character was part of an operator like "&&"
+        let mut operator = false;
+
+        for (i, ch) in line.char_indices() {
+            let continues_operator = std::mem::replace(&mut operator, false);
+
+            if escaped {
+                word.text.push(ch);
+                escaped = false;
+                continue;
+            }
+
+            match (quote, ch) {
+                (Some(q), ch) if ch == q => quote = None,
+                (Some(_), ch) => word.text.push(ch),
+                (None, '\\') => escaped = true,
+                (None, '\'') | (None, '"') => {
+                    if word.text.is_empty() {
+                        word.quote = Some(ch);
+                    }
+                    quote = Some(ch);
+                }
+                // Same as for the substitutions, so "ls|gr" is two commands
+                (None, ch) if ch.is_ascii() && crate::shell::is_separator(ch as u8) => {
+                    if !word.text.is_empty() {
+                        words.push(std::mem::take(&mut word.text));
+                    }
+                    if !ch.is_ascii_whitespace() {
+                        match words.last_mut() {
+                            Some(last) if continues_operator => last.push(ch),
+                            _ => words.push(ch.to_string()),
+                        }
+                        operator = true;
+                    }
+                    word.start = i + 1;
+                    word.quote = None;
+                }
+                (None, ch) => word.text.push(ch),
+            }
+        }
+
+        word.command = match words.last() {
+            Some(last) => OPERATORS.contains(&last.as_str()),
+            None => true,
+        };
+        word.before = words;
+
+        word
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Completion {
+    // Where the completed word starts in the input
+    pub start: usize,
+    // The word as typed, without quotes
+    text: String,
+    candidates: Vec<Candidate>,
+    selection: usize,
+    quote: Option<char>,
+}
+
+impl Completion {
+    // Candidates for the word before the cursor, depending on what it
+    // looks like. Only the exec prompt runs commands, the command prompt
+    // has its own little language.
+    pub fn new(query: &str, line: &str) -> Option<Completion> {
+        if query == "jump" {
+            return Completion::jump(line);
+        }
+
+        let word = Word::parse(line);
+        let text = word.text.as_str();
+
+        let mut candidates = if query == "command" {
+            command_candidates(&word.before, text)
+        } else if let Some(name) = text.strip_prefix('$') {
+            substitution_candidates(name)
+        } else if let Some(key) = text.strip_prefix('~').filter(|_| !text.contains('/')) {
+            bookmark_candidates(key)
+        } else if word.command && query == "exec" && !text.contains('/') {
+            bin_candidates(text)
+        } else {
+            path_candidates(text)
+        };
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        candidates.sort_by(|a, b| natord::compare(&a.label, &b.label));
+        candidates.dedup_by(|a, b| a.value == b.value);
+
+        Some(Completion {
+            start: word.start,
+            text: word.text,
+            candidates,
+            selection: 0,
+            quote: word.quote,
+        })
+    }
+
+    // The whole line is the query, and the order is the frecency ranking
+    fn jump(line: &str) -> Option<Completion> {
+        let candidates = crate::frecency::query(line)
+            .ok()?
+            .into_iter()
+            .map(|path| Candidate::verbatim(path.to_string_lossy().to_string(), None))
+            .collect::<Vec<_>>();
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        Some(Completion {
+            start: 0,
+            text: line.to_string(),
+            candidates,
+            selection: 0,
+            quote: None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn next(&mut self) {
+        self.selection = (self.selection + 1) % self.len();
+    }
+
+    pub fn prev(&mut self) {
+        self.selection = (self.selection + self.len() - 1) % self.len();
+    }
+
+    // Keeps the selection when the candidates are updated while typing
+    pub fn select_label(&mut self, label: &str) {
+        if let Some(pos) = self.candidates.iter().position(|c| c.label == label) {
+            self.selection = pos;
+        }
+    }
+
+    pub fn selected(&self) -> &Candidate {
+        &self.candidates[self.selection]
+    }
+
+    pub fn replacement(&self) -> String {
+        let candidate = self.selected();
+
+        match candidate.quote {
+            true => quote(&candidate.value, self.quote, true),
+            false => candidate.value.clone(),
+        }
+    }
+
+    // Longest start shared by all candidates, with the quote left open
+    pub fn common_prefix(&self) -> Option<String> {
+        let first = &self.candidates.first()?.value;
+        let len = self.candidates.iter().fold(first.len(), |len, candidate| {
+            first[..len]
+                .char_indices()
+                .zip(candidate.value.chars())
+                .find(|((_, a), b)| a != b)
+                .map(|((i, _), _)| i)
+                .unwrap_or_else(|| len.min(candidate.value.len()))
+        });
+
+        // Bookmarks replace the word with something else entirely
+        let prefix = &first[..len];
+        if prefix.len() <= self.text.len() || !prefix.starts_with(&self.text) {
+            return None;
+        }
+
+        match self.candidates.iter().all(|candidate| candidate.quote) {
+            true => Some(quote(prefix, self.quote, false)),
+            false => Some(prefix.to_string()),
+        }
+    }
+
+    // Tiny terminals get a smaller menu
+    fn rows(&self, ypos: u16) -> usize {
+        self.len().min(MAX_ROWS).min(ypos.saturating_sub(1) as usize)
+    }
+
+    // Drawn right above the line at ypos
+    pub fn render(&self, xpos: u16, ypos: u16, xsize: usize) -> String {
+        let rows = self.rows(ypos);
+        let offset = (self.selection + 1).saturating_sub(rows);
+        let label_width = self
+            .candidates
+            .iter()
+            .map(|candidate| term::string_len(&candidate.label))
+            .max()
+            .unwrap_or(0)
+            .min(xsize / 2);
+
+        self.candidates
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(rows)
+            .enumerate()
+            .map(|(row, (i, candidate))| {
+                let line = match &candidate.info {
+                    Some(info) => format!("{:width$}  {}{}",
+                                          term::sized_string_u(&candidate.label, label_width),
+                                          term::color_light_black(),
+                                          info,
+                                          width = label_width),
+                    None => candidate.label.clone(),
+                };
+                let selected = match i == self.selection {
+                    true => term::invert(),
+                    false => String::new(),
+                };
+
+                format!("{}{}{}{}{}",
+                        term::goto_xy(xpos, ypos - (rows - row) as u16),
+                        termion::clear::CurrentLine,
+                        selected,
+                        term::sized_string_u(&line, xsize),
+                        term::reset())
+            })
+            .collect()
+    }
+
+    pub fn clearlist(&self, xpos: u16, ypos: u16) -> String {
+        (1..=self.rows(ypos))
+            .map(|row| format!("{}{}",
+                               term::goto_xy(xpos, ypos - row as u16),
+                               termion::clear::CurrentLine))
+            .collect()
+    }
+}
+
+// Names with spaces and other special characters are quoted the same way
+// the word was, or escaped with backslashes
+fn quote(text: &str, quote: Option<char>, close: bool) -> String {
+    let (quoted, end) = match quote {
+        Some('\'') => (text.replace('\'', "'\\''"), "'"),
+        Some(_) => {
+            let quoted = text.chars().fold(String::new(), |mut quoted, ch| {
+                if "\"\\$`".contains(ch) {
+                    quoted.push('\\');
+                }
+                quoted.push(ch);
+                quoted
+            });
+            (quoted, "\"")
+        }
+        None => {
+            // A leading ~/ still has to expand
+            let (home, rest) = match text.starts_with("~/") {
+                true => text.split_at(2),
+                false => ("", text),
+            };
+            let escaped = rest.chars().fold(home.to_string(), |mut escaped, ch| {
+                if SPECIAL_CHARS.contains(ch) {
+                    escaped.push('\\');
+                }
+                escaped.push(ch);
+                escaped
+            });
+            return escaped;
+        }
+    };
+
+    match (quote, close) {
+        (Some(q), true) => format!("{}{}{}", q, quoted, end),
+        (Some(q), false) => format!("{}{}", q, quoted),
+        _ => quoted,
+    }
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match (path.starts_with("~/"), crate::paths::home_path()) {
+        (true, Ok(home)) => home.join(&path[2..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn path_candidates(word: &str) -> Vec<Candidate> {
+    let (dir_part, name_part) = match word.rfind('/') {
+        Some(pos) => word.split_at(pos + 1),
+        None => ("", word),
+    };
+    let dir = match dir_part {
+        "" => PathBuf::from("."),
+        dir => expand_tilde(dir),
+    };
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            // Hidden files only when asked for
+            if !name.starts_with(name_part) || (name.starts_with('.') && !name_part.starts_with('.')) {
+                return None;
+            }
+
+            let is_dir = entry.path().is_dir();
+            let label = match is_dir {
+                true => format!("{}/", name),
+                false => name,
+            };
+
+            Some(Candidate::new(format!("{}{}", dir_part, label), label, None))
+        })
+        .collect()
+}
+
+fn word_candidates(words: &[&str], word: &str) -> Vec<Candidate> {
+    words
+        .iter()
+        .filter(|name| name.starts_with(word))
+        .map(|name| Candidate::verbatim(name.to_string(), None))
+        .collect()
+}
+
+// Commands and action names first, then whatever the command takes
+fn command_candidates(before: &[String], word: &str) -> Vec<Candidate> {
+    use crate::cmdline::{COMMANDS, SUBCOMMANDS};
+
+    let before = before.iter().map(String::as_str).collect::<Vec<_>>();
+    let subcommands = |command| {
+        SUBCOMMANDS
+            .iter()
+            .find(|(name, _)| *name == command)
+            .map(|(_, subcommands)| *subcommands)
+            .unwrap_or(&[])
+    };
+
+    match before.as_slice() {
+        [] => {
+            let commands = COMMANDS
+                .iter()
+                .filter(|(name, _)| name.starts_with(word))
+                .map(|(name, info)| Candidate::verbatim(name.to_string(), Some(info.to_string())));
+            let actions = crate::palette::entries(&crate::keybind::KeyBinds::default())
+                .into_iter()
+                .filter(|entry| entry.name.to_lowercase().starts_with(&word.to_lowercase()))
+                .map(|entry| Candidate::verbatim(entry.name, Some(entry.section.to_string())));
+
+            commands.chain(actions).collect()
+        }
+        ["cd"] | ["tab", "new"] => path_candidates(word),
+        ["sort", ..] => word_candidates(subcommands("sort"), word),
+        ["bookmark"] => {
+            let mut candidates = word_candidates(subcommands("bookmark"), word);
+            candidates.extend(crate::bookmarks::Bookmarks::new()
+                              .iter()
+                              .filter(|bm| bm.key.starts_with(word))
+                              .map(|bm| Candidate::verbatim(bm.key.clone(), Some(bm.path.clone()))));
+            candidates
+        }
+        ["bookmark", "del"] => {
+            crate::bookmarks::Bookmarks::new()
+                .iter()
+                .filter(|bm| bm.key.starts_with(word))
+                .map(|bm| Candidate::verbatim(bm.key.clone(), Some(bm.path.clone())))
+                .collect()
+        }
+        [command @ "select"] | [command @ "tab"] => word_candidates(subcommands(command), word),
+        ["set"] => {
+            let options = crate::config::Config::OPTIONS
+                .iter()
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>();
+            word_candidates(&options, word)
+        }
+        ["set", option] => {
+            let values = crate::config::Config::OPTIONS
+                .iter()
+                .find(|(name, _)| name == option)
+                .map(|(_, values)| *values)
+                .unwrap_or(&[]);
+            word_candidates(values, word)
+        }
+        _ => vec![],
+    }
+}
+
+fn bin_candidates(word: &str) -> Vec<Candidate> {
+    crate::minibuffer::find_bins(word)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|bin| {
+            let bin = bin.to_string_lossy().to_string();
+            Candidate::new(bin.clone(), bin, None)
+        })
+        .collect()
+}
+
+fn substitution_candidates(word: &str) -> Vec<Candidate> {
+    let patterns = patterns()
+        .into_iter()
+        .filter(|(pattern, _)| pattern[1..].starts_with(word))
+        .map(|(pattern, info)| Candidate::verbatim(pattern, Some(info)));
+
+    let vars = std::env::vars()
+        .filter(|(name, _)| name.starts_with(word))
+        .map(|(name, value)| Candidate::verbatim(format!("${}", name), Some(value)));
+
+    patterns.chain(vars).collect()
+}
+
+// Like named directories in zsh, ~key is replaced with the bookmark's path
+fn bookmark_candidates(word: &str) -> Vec<Candidate> {
+    let bookmarks = crate::bookmarks::Bookmarks::new();
+
+    bookmarks
+        .iter()
+        .filter(|bm| {
+            bm.key.starts_with(word) ||
+            bm.name.as_ref().map(|name| name.starts_with(word)).unwrap_or(false)
+        })
+        .map(|bm| {
+            let info = match &bm.name {
+                Some(name) => format!("{} {}", name, bm.path),
+                None => bm.path.clone(),
+            };
+            let path = format!("{}/", bm.path.trim_end_matches('/'));
+
+            Candidate::new(path, format!("~{}", bm.key), Some(info))
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_word() {
+    let word = Word::parse("ls -l \"some fi");
+    assert_eq!((word.start, word.text.as_str(), word.quote, word.command),
+               (6, "some fi", Some('"'), false));
+    assert_eq!(word.before, vec!["ls", "-l"]);
+
+    let word = Word::parse("ls\tsome\\ fi");
+    assert_eq!((word.start, word.text.as_str()), (3, "some fi"));
+
+    // Operators start a new command, with or without spaces
+    let word = Word::parse("ls|gr");
+    assert_eq!((word.start, word.text.as_str(), word.command), (3, "gr", true));
+    let word = Word::parse("make&&ca");
+    assert_eq!(word.before, vec!["make", "&&"]);
+    assert!(word.command);
+
+    let word = Word::parse("sort<da");
+    assert_eq!((word.text.as_str(), word.command), ("da", false));
+}
+
+#[test]
+fn test_quote() {
+    assert_eq!(quote("a b's", None, true), "a\\ b\\'s");
+    assert_eq!(quote("~/a b", None, true), "~/a\\ b");
+    assert_eq!(quote("a b's", Some('\''), true), "'a b'\\''s'");
+    assert_eq!(quote("a $b", Some('"'), false), "\"a \\$b");
+}
+
+#[test]
+fn test_common_prefix() {
+    let completion = |text: &str, values: &[&str], quote| Completion {
+        start: 0,
+        text: text.to_string(),
+        candidates: values.iter()
+            .map(|value| Candidate::new(value.to_string(), value.to_string(), None))
+            .collect(),
+        selection: 0,
+        quote,
+    };
+
+    assert_eq!(completion("f", &["foo bar", "foo baz"], None).common_prefix(),
+               Some("foo\\ ba".to_string()));
+    assert_eq!(completion("f", &["foo bar", "foo baz"], Some('\'')).common_prefix(),
+               Some("'foo ba".to_string()));
+    assert_eq!(completion("fo", &["föo", "fäo"], None).common_prefix(), None);
+    assert_eq!(completion("foo", &["foo", "foobar"], None).common_prefix(), None);
+    assert_eq!(completion("f", &["foo", "foobar"], None).common_prefix(),
+               Some("foo".to_string()));
+}
@@ -80,6 +80,41 @@ fn infuse_argv_config(mut config: Config) -> Config {
     config
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineNumbers {
+    Off,
+    Absolute,
+    Relative,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    Miller,
+    DualPane,
+}
+
+// Same names as the layout option and in session files
+impl std::str::FromStr for Layout {
+    type Err = WError;
+
+    fn from_str(layout: &str) -> WResult<Layout> {
+        match layout {
+            "miller" => Ok(Layout::Miller),
+            "dual" => Ok(Layout::DualPane),
+            _ => WError::config_error(format!("layout={}", layout)),
+        }
+    }
+}
+
+impl std::fmt::Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Layout::Miller => write!(f, "miller"),
+            Layout::DualPane => write!(f, "dual"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub animation: bool,
@@ -89,12 +124,24 @@ pub struct Config {
     pub cd_cmd: String,
     pub icons: bool,
     pub icons_space: bool,
+    pub line_numbers: LineNumbers,
     pub media_autoplay: bool,
     pub media_mute: bool,
     pub media_previewer: String,
     pub media_previewer_exists: bool,
     pub ratios: Vec::<usize>,
+    pub parent_columns: usize,
+    pub layout: Layout,
+    // Widths below which parent columns and then the preview are hidden
+    pub breakpoints: Option<(usize, usize)>,
+    pub tag_colors: Vec<(char, String)>,
     pub graphics: String,
+    // Entries kept for each prompt
+    pub history_size: usize,
+    // Entries from the current directory come first
+    pub history_dir_ranking: bool,
+    // Milliseconds a key sequence waits for its next key
+    pub key_timeout: Option<u64>,
     pub keybinds: KeyBinds,
 }
 
@@ -115,12 +162,20 @@ impl Config {
             cd_cmd: "find -type d | fzf".to_string(),
             icons: false,
             icons_space: false,
+            line_numbers: LineNumbers::Off,
             media_autoplay: false,
             media_mute: false,
             media_previewer: "hunter-media".to_string(),
             media_previewer_exists: false,
             ratios: vec![20,30,49],
+            parent_columns: 1,
+            layout: Layout::Miller,
+            breakpoints: Some((80, 40)),
+            tag_colors: vec![(crate::files::DEFAULT_TAG, crate::term::color_red())],
             graphics: detect_g_mode(),
+            history_size: 1000,
+            history_dir_ranking: false,
+            key_timeout: Some(1000),
             keybinds: KeyBinds::default(),
         }
     }
@@ -134,65 +189,10 @@ impl Config {
 
         let config_string = std::fs::read_to_string(config_path)?;
 
-        let config = config_string.lines().fold(Config::new(), |mut config, line| {
-            match Config::prep_line(line) {
-                Ok(("animation", "on")) => config.animation = true,
-                Ok(("animation", "off")) => config.animation = false,
-                Ok(("animation_refresh_frequency", frequency)) => {
-                    match frequency.parse::<usize>() {
-                        Ok(parsed_freq) => config.animation_refresh_frequency = parsed_freq,
-                        _ => WError::config_error::<Config>(line.to_string()).log()
-                    }
-                }
-                Ok(("show_hidden", "on")) => config.show_hidden = true,
-                Ok(("show_hidden", "off")) => config.show_hidden = false,
-                Ok(("icons", "on")) => config.icons = true,
-                Ok(("icons", "off")) => config.icons = false,
-                Ok(("icons_space", "on")) => config.icons_space = true,
-                Ok(("icons_space", "off")) => config.icons_space = false,
-                Ok(("select_cmd", cmd)) => {
-                    let cmd = cmd.to_string();
-                    config.select_cmd = cmd;
-                }
-                Ok(("cd_cmd", cmd)) => {
-                    let cmd = cmd.to_string();
-                    config.cd_cmd = cmd;
-                }
-                Ok(("media_autoplay", "on")) => config.media_autoplay = true,
-                Ok(("media_autoplay", "off")) => config.media_autoplay = false,
-                Ok(("media_mute", "on")) => config.media_mute = true,
-                Ok(("media_mute", "off")) => config.media_mute = false,
-                Ok(("media_previewer", cmd)) => {
-                    let cmd = cmd.to_string();
-                    config.media_previewer = cmd;
-                },
-                Ok(("ratios", ratios)) => {
-                    let ratios_str = ratios.to_string();
-                    if ratios_str.chars().all(|x| x.is_digit(10) || x.is_whitespace()
-                        || x == ':' || x == ',' ) {
-                        let ratios: Vec<usize> = ratios_str.split([',', ':'].as_ref())
-                            .map(|r| r.trim()
-                                 .parse().unwrap())
-                            .collect();
-                        let ratios_sum: usize = ratios.iter().sum();
-                        if ratios.len() == 3 && ratios_sum > 0 &&
-                            ratios
-                            .iter()
-                            .filter(|&r| *r > u16::max_value() as usize)
-                            .next() == None {
-                                config.ratios = ratios;
-                            }
-                    }
-                }
-                #[cfg(feature = "sixel")]
-                Ok(("graphics",
-                    "sixel")) => config.graphics = "sixel".to_string(),
-                Ok(("graphics",
-                    "kitty")) => config.graphics = "kitty".to_string(),
-                Ok(("graphics",
-                    "auto")) => config.graphics = detect_g_mode(),
-                _ => { WError::config_error::<Config>(line.to_string()).log(); }
-            }
+        let config = Config::setting_lines(&config_string).fold(Config::new(), |mut config, (_, line)| {
+            Config::prep_line(line)
+                .and_then(|(name, value)| config.set(name, value))
+                .log();
 
             #[cfg(feature = "img")]
             match has_media_previewer(&config.media_previewer) {
@@ -203,6 +203,7 @@ impl Config {
         });
 
         let mut config = infuse_argv_config(config);
+        config.check_ratios().log();
 
         //use std::iter::Extend;
         KeyBinds::load()
@@ -212,7 +213,194 @@ impl Config {
         Ok(config)
     }
 
-    fn prep_line<'a>(line: &'a str) -> WResult<(&'a str, &'a str)> {
+    // What :set completes to, with the values that aren't free form
+    pub const OPTIONS: [(&'static str, &'static [&'static str]); 20] = [
+        ("animation", &["on", "off"]),
+        ("animation_refresh_frequency", &[]),
+        ("show_hidden", &["on", "off"]),
+        ("icons", &["on", "off"]),
+        ("icons_space", &["on", "off"]),
+        ("line_numbers", &["off", "absolute", "relative"]),
+        ("select_cmd", &[]),
+        ("cd_cmd", &[]),
+        ("media_autoplay", &["on", "off"]),
+        ("media_mute", &["on", "off"]),
+        ("media_previewer", &[]),
+        ("ratios", &[]),
+        ("parent_columns", &[]),
+        ("layout", &["miller", "dual"]),
+        ("breakpoints", &["off"]),
+        ("tag_colors", &[]),
+        ("history_size", &[]),
+        ("history_dir_ranking", &["on", "off"]),
+        ("key_timeout", &["off"]),
+        ("graphics", &["auto", "kitty", "sixel"]),
+    ];
+
+    // One "name=value" line of the config file, also used by :set
+    pub fn set(&mut self, name: &str, value: &str) -> WResult<()> {
+        match (name, value) {
+            ("animation", "on") => self.animation = true,
+            ("animation", "off") => self.animation = false,
+            ("animation_refresh_frequency", frequency) => {
+                match frequency.parse::<usize>() {
+                    Ok(parsed_freq) => self.animation_refresh_frequency = parsed_freq,
+                    _ => return WError::config_error(format!("{}={}", name, value))
+                }
+            }
+            ("show_hidden", "on") => self.show_hidden = true,
+            ("show_hidden", "off") => self.show_hidden = false,
+            ("icons", "on") => self.icons = true,
+            ("icons", "off") => self.icons = false,
+            ("icons_space", "on") => self.icons_space = true,
+            ("icons_space", "off") => self.icons_space = false,
+            ("line_numbers", "off") => self.line_numbers = LineNumbers::Off,
+            ("line_numbers", "absolute") => self.line_numbers = LineNumbers::Absolute,
+            ("line_numbers", "relative") => self.line_numbers = LineNumbers::Relative,
+            ("select_cmd", cmd) => {
+                let cmd = cmd.to_string();
+                self.select_cmd = cmd;
+            }
+            ("cd_cmd", cmd) => {
+                let cmd = cmd.to_string();
+                self.cd_cmd = cmd;
+            }
+            ("media_autoplay", "on") => self.media_autoplay = true,
+            ("media_autoplay", "off") => self.media_autoplay = false,
+            ("media_mute", "on") => self.media_mute = true,
+            ("media_mute", "off") => self.media_mute = false,
+            ("media_previewer", cmd) => {
+                let cmd = cmd.to_string();
+                self.media_previewer = cmd;
+            },
+            ("ratios", ratios) => {
+                // "1,2", "1:2" or "1 2", but "1,,2" is still missing one
+                let ratios = ratios.split([',', ':'].as_ref())
+                    .flat_map(|part| match part.trim() {
+                        "" => vec![""],
+                        part => part.split_whitespace().collect()
+                    })
+                    .map(|r| r.parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>();
+
+                // At least two, not all zero and each fits the terminal size
+                match ratios {
+                    Ok(ratios) if ratios.len() >= 2 &&
+                        ratios.iter().sum::<usize>() > 0 &&
+                        !ratios.iter().any(|&r| r > u16::MAX as usize) => self.ratios = ratios,
+                    _ => return WError::config_error(format!("{}={}", name, value))
+                }
+            }
+            ("parent_columns", columns) => {
+                match columns.parse::<usize>() {
+                    Ok(columns) => self.parent_columns = columns,
+                    _ => return WError::config_error(format!("{}={}", name, value))
+                }
+            }
+            ("layout", layout) => self.layout = layout.parse()?,
+            ("breakpoints", "off") => self.breakpoints = None,
+            ("breakpoints", breakpoints) => {
+                let breakpoints = breakpoints.split(',')
+                                             .map(|b| b.trim().parse::<usize>())
+                                             .collect::<Result<Vec<_>, _>>();
+                match breakpoints.as_ref().map(|b| b.as_slice()) {
+                    Ok(&[narrow, single]) if narrow >= single => {
+                        self.breakpoints = Some((narrow, single))
+                    }
+                    _ => return WError::config_error(format!("{}={}", name, value))
+                }
+            }
+            ("tag_colors", colors) => {
+                for color in colors.split(',') {
+                    let mut chars = color.trim().chars();
+                    let label = chars.next();
+                    let color = match chars.next() {
+                        Some(':') => crate::term::parse_color(chars.as_str()),
+                        _ => None
+                    };
+
+                    match (label, color) {
+                        (Some(label), Some(color)) => {
+                            self.tag_colors.retain(|(l, _)| *l != label);
+                            self.tag_colors.push((label, color));
+                        }
+                        _ => return WError::config_error(format!("{}={}", name, value))
+                    }
+                }
+            }
+            ("history_size", size) => {
+                match size.parse::<usize>() {
+                    Ok(size) => self.history_size = size,
+                    _ => return WError::config_error(format!("{}={}", name, value))
+                }
+            }
+            ("history_dir_ranking", "on") => self.history_dir_ranking = true,
+            ("history_dir_ranking", "off") => self.history_dir_ranking = false,
+            ("key_timeout", "off") => self.key_timeout = None,
+            ("key_timeout", timeout) => {
+                match timeout.parse::<u64>() {
+                    Ok(timeout) => self.key_timeout = Some(timeout),
+                    _ => return WError::config_error(format!("{}={}", name, value))
+                }
+            }
+            #[cfg(feature = "sixel")]
+            ("graphics", "sixel") => self.graphics = "sixel".to_string(),
+            ("graphics", "kitty") => self.graphics = "kitty".to_string(),
+            ("graphics", "auto") => self.graphics = detect_g_mode(),
+            _ => return WError::config_error(format!("{}={}", name, value)),
+        }
+
+        Ok(())
+    }
+
+    // One ratio for each parent column, plus main and preview column
+    // What :set uses, the running config only changes if the result is usable
+    pub fn with_setting(&self, name: &str, value: &str) -> WResult<Config> {
+        let mut config = self.clone();
+        config.set(name, value)?;
+
+        // The old ratios are for a different number of columns
+        if config.parent_columns != self.parent_columns {
+            config.ratios = Config::default_ratios(config.parent_columns);
+        }
+
+        config.check_ratios()?;
+        Ok(config)
+    }
+
+    pub fn check_ratios(&mut self) -> WResult<()> {
+        if self.ratios.len() != self.parent_columns + 2 {
+            let ratios = self.ratios
+                             .iter()
+                             .map(|r| r.to_string())
+                             .collect::<Vec<_>>()
+                             .join(",");
+            self.ratios = Config::default_ratios(self.parent_columns);
+            return WError::config_error(format!("ratios={}", ratios));
+        }
+
+        Ok(())
+    }
+
+    fn default_ratios(parent_columns: usize) -> Vec<usize> {
+        match parent_columns {
+            1 => vec![20,30,49],
+            _ => std::iter::repeat_n(15, parent_columns)
+                .chain(vec![30,49])
+                .collect()
+        }
+    }
+
+    // The lines of a config file that aren't blank or comments, numbered
+    // from 1 like in an editor
+    pub fn setting_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+        text.lines()
+            .enumerate()
+            .map(|(n, line)| (n + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+    }
+
+    pub fn prep_line(line: &str) -> WResult<(&str, &str)> {
         let setting = line.split("=").collect::<Vec<&str>>();
         if setting.len() == 2 {
             Ok((setting[0], setting[1]))
@@ -233,6 +421,19 @@ impl Config {
     pub fn media_available(&self) -> bool {
         self.media_previewer_exists
     }
+
+    // Labels without a configured color get one from a fixed palette
+    pub fn tag_color(&self, label: char) -> String {
+        const PALETTE: [&str; 6] = ["yellow", "green", "cyan", "magenta", "blue", "light_red"];
+
+        match self.tag_colors.iter().find(|(l, _)| *l == label) {
+            Some((_, color)) => color.clone(),
+            None => {
+                let color = PALETTE[label as usize % PALETTE.len()];
+                crate::term::parse_color(color).unwrap_or_else(crate::term::color_red)
+            }
+        }
+    }
 }
 
 fn detect_g_mode() -> String {
@@ -253,3 +454,32 @@ fn has_media_previewer(name: &str) -> bool {
         false => find_bins(name).is_ok()
     }
 }
+
+#[test]
+fn test_set_ratios() {
+    let mut config = Config::default();
+
+    config.set("ratios", "20, 30:50").unwrap();
+    assert_eq!(config.ratios, vec![20, 30, 50]);
+
+    for bad in ["1,,2", "abc", "0,0", "5", "1,70000", "1,-2"] {
+        assert!(config.set("ratios", bad).is_err(), "{}", bad);
+    }
+    assert_eq!(config.ratios, vec![20, 30, 50]);
+}
+
+#[test]
+fn test_with_setting() {
+    let config = Config::default();
+
+    let wider = config.with_setting("parent_columns", "2").unwrap();
+    assert_eq!(wider.parent_columns, 2);
+    assert_eq!(wider.ratios, vec![15, 15, 30, 49]);
+
+    let ratios = wider.with_setting("ratios", "1 2 3 4").unwrap();
+    assert_eq!(ratios.ratios, vec![1, 2, 3, 4]);
+
+    // Wrong count for the columns, nothing changes
+    assert!(ratios.with_setting("ratios", "1,2,3").is_err());
+    assert_eq!(ratios.ratios, vec![1, 2, 3, 4]);
+}
@@ -0,0 +1,420 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::str::FromStr;
+
+use ini::Ini;
+
+use crate::config::Config;
+use crate::fail::WResult;
+use crate::keybind::*;
+
+// Problems are printed as they are found, only the counts are kept
+struct Report {
+    errors: usize,
+    warnings: usize,
+}
+
+impl Report {
+    fn error(&mut self, place: &str, msg: String) {
+        println!("{}: error: {}", place, msg);
+        self.errors += 1;
+    }
+
+    fn warning(&mut self, place: &str, msg: String) {
+        println!("{}: warning: {}", place, msg);
+        self.warnings += 1;
+    }
+}
+
+// For --check-config, returns the exit code
+pub fn run() -> i32 {
+    let mut report = Report { errors: 0, warnings: 0 };
+
+    match check_all(&mut report) {
+        Ok(_) => {}
+        Err(err) => report.error("wandex", err.to_string()),
+    }
+
+    println!("{} errors, {} warnings", report.errors, report.warnings);
+
+    match report.errors {
+        0 => 0,
+        _ => 1,
+    }
+}
+
+fn check_all(report: &mut Report) -> WResult<()> {
+    let dir = crate::paths::wandex_path()?;
+    println!("Checking {}", dir.to_string_lossy());
+
+    if !dir.exists() {
+        report.warning("wandex", String::from("No config yet, the default one is installed on the next start"));
+        return Ok(());
+    }
+
+    check_config(report)?;
+    check_keys(report)?;
+    check_tree(report, "actions", &crate::paths::actions_path()?)?;
+    check_tree(report, "previewers", &crate::paths::previewers_path()?)?;
+
+    Ok(())
+}
+
+// The same lines Config::load would log, plus options set twice
+fn check_config(report: &mut Report) -> WResult<()> {
+    let path = crate::paths::config_path()?;
+
+    if !path.exists() {
+        report.warning("config", String::from("Not found, the defaults are used"));
+        return Ok(());
+    }
+
+    let text = std::fs::read_to_string(path)?;
+    check_config_text(report, &text);
+
+    Ok(())
+}
+
+fn check_config_text(report: &mut Report, text: &str) {
+    let mut config = Config::default();
+    let mut seen: Vec<(&str, usize)> = vec![];
+
+    for (n, line) in Config::setting_lines(text) {
+        let place = format!("config:{}", n);
+
+        let (name, value) = match Config::prep_line(line) {
+            Ok(setting) => setting,
+            Err(_) => {
+                report.error(&place, format!("Expected option=value, got \"{}\"", line));
+                continue;
+            }
+        };
+
+        if !Config::OPTIONS.iter().any(|(option, _)| *option == name) {
+            report.error(&place, format!("Unknown option {}", name));
+            continue;
+        }
+
+        if config.set(name, value).is_err() {
+            report.error(&place, format!("Bad value for {}: {}", name, value));
+        }
+
+        match seen.iter().find(|(option, _)| *option == name) {
+            Some((_, line)) => {
+                report.warning(&place, format!("{} was already set on line {}", name, line))
+            }
+            None => seen.push((name, n)),
+        }
+    }
+
+    if config.check_ratios().is_err() {
+        report.error("config", format!("ratios needs {} numbers with parent_columns={}",
+                                       config.parent_columns + 2,
+                                       config.parent_columns));
+    }
+}
+
+// The last line wins when keys are bound more than once in a section
+fn check_bindings(report: &mut Report,
+                  place: &str,
+                  bindings: Vec<(String, Vec<AnyKey>)>) {
+    let mut checked: Vec<&Vec<AnyKey>> = vec![];
+
+    for (_, keys) in &bindings {
+        if checked.contains(&keys) {
+            continue;
+        }
+        checked.push(keys);
+
+        let actions = bindings
+            .iter()
+            .filter(|(_, other)| other == keys)
+            .map(|(action, _)| action.as_str())
+            .collect::<Vec<_>>();
+        let mut distinct = actions.clone();
+        distinct.sort();
+        distinct.dedup();
+
+        match (actions.len(), distinct.len()) {
+            (1, _) => {}
+            (_, 1) => {
+                report.warning(place, format!("{} is bound to {} more than once",
+                                              keys_to_string(keys), actions[0]))
+            }
+            (_, n) => {
+                report.error(place, format!("{} is bound to {} and {}, only {} works",
+                                            keys_to_string(keys),
+                                            distinct[..n - 1].join(", "),
+                                            distinct[n - 1],
+                                            actions[actions.len() - 1]))
+            }
+        }
+    }
+}
+
+fn parse_bindings(report: &mut Report,
+                  place: &str,
+                  action: &str,
+                  keys_str: &str) -> Vec<(String, Vec<AnyKey>)> {
+    keys_str
+        .split(',')
+        .map(str::trim)
+        .filter_map(|key_str| match parse_keys(key_str) {
+            Ok(keys) => Some((action.to_string(), keys)),
+            Err(_) => {
+                report.error(place, format!("Invalid key \"{}\" for {}", key_str, action));
+                None
+            }
+        })
+        .collect()
+}
+
+fn check_section<T>(report: &mut Report, ini: &Ini) -> &'static str
+where
+    T: BindingSection,
+    Bindings<T>: Default,
+{
+    let place = format!("keys [{}]", T::section());
+    let section = match ini.section(Some(T::section())) {
+        Some(section) => section,
+        None => return T::section(),
+    };
+
+    let mut bindings = vec![];
+
+    for (action_str, keys_str) in section.iter() {
+        let (name, _) = T::process_action_str(action_str);
+
+        match T::from_str(name) {
+            Ok(_) => bindings.extend(parse_bindings(report, &place, action_str, keys_str)),
+            Err(_) => report.error(&place, format!("Unknown action {}", action_str)),
+        }
+    }
+
+    check_bindings(report, &place, bindings);
+    T::section()
+}
+
+fn check_commands(report: &mut Report, ini: &Ini) {
+    let place = "keys [commands]";
+    let section = match ini.section(Some("commands")) {
+        Some(section) => section,
+        None => return,
+    };
+
+    let filebrowser = FileBrowserAction::load_section(ini);
+    let mut bindings = vec![];
+
+    for (keys_str, command_str) in section.iter() {
+        let command = match UserCommand::from_str(command_str) {
+            Ok(command) => command,
+            Err(err) => {
+                report.error(place, err.to_string());
+                continue;
+            }
+        };
+
+        for (cmd, keys) in parse_bindings(report, place, &command.cmd, keys_str) {
+            if let Some(action) = filebrowser.iter().find(|(bound, _)| *bound == keys).map(|(_, action)| action) {
+                report.warning(place, format!("{} replaces {} from [filebrowser] with {}",
+                                              keys_to_string(&keys), action, cmd));
+            }
+            bindings.push((cmd, keys));
+        }
+    }
+
+    check_bindings(report, place, bindings);
+}
+
+// A placeholder like "_" covers every key it stands for
+fn covers(outer: &[AnyKey], inner: &[AnyKey]) -> bool {
+    outer.len() <= inner.len()
+        && outer.iter().zip(inner).all(|(outer, inner)| {
+            outer == inner || inner.any().as_ref() == Some(outer)
+        })
+}
+
+fn bound<T>(bindings: &Bindings<T>, name: impl Fn(&T) -> String) -> Vec<(String, Vec<AnyKey>)> {
+    let mut bound = bindings
+        .iter()
+        .map(|(keys, action)| (name(action), keys))
+        .collect::<Vec<_>>();
+    bound.sort_by_key(|(action, keys)| (keys_to_string(keys), action.clone()));
+    bound
+}
+
+// Keys go through the tabs first, then the file browser, which moves
+// before doing anything else, and then the file list
+fn check_shadowed(report: &mut Report, keybinds: &KeyBinds) {
+    let chain = [
+        ("tabs", bound(&keybinds.tab, |action| action.to_string())),
+        ("movement", bound(&keybinds.movement, |action| action.to_string())),
+        ("filebrowser", bound(&keybinds.filebrowser, |action| keybinds.filebrowser_name(action))),
+        ("filelist", bound(&keybinds.filelist, |action| action.to_string())),
+    ];
+
+    for (i, (inner_section, inner)) in chain.iter().enumerate() {
+        for (action, keys) in inner {
+            // In the dual pane layout it's the other way round
+            let captured = *inner_section == "filebrowser" && action == "SwitchPane";
+
+            let shadowing = chain[..i].iter().find_map(|(outer_section, outer)| {
+                if captured && *outer_section == "tabs" {
+                    return None;
+                }
+
+                outer.iter()
+                     .find(|(_, outer_keys)| covers(outer_keys, keys))
+                     .map(|(outer_action, _)| (outer_section, outer_action))
+            });
+
+            if let Some((outer_section, outer_action)) = shadowing {
+                report.warning(&format!("keys [{}]", inner_section),
+                               format!("{} for {} never gets there, it's {} in [{}]",
+                                       keys_to_string(keys), action, outer_action, outer_section));
+            }
+        }
+    }
+}
+
+fn check_keys(report: &mut Report) -> WResult<()> {
+    let path = crate::paths::bindings_path()?;
+
+    if !path.exists() {
+        report.warning("keys", String::from("Not found, the defaults are used"));
+        check_shadowed(report, &KeyBinds::default());
+        return Ok(());
+    }
+
+    let ini = match Ini::load_from_file_noescape(&path) {
+        Ok(ini) => ini,
+        Err(err) => {
+            report.error("keys", err.to_string());
+            return Ok(());
+        }
+    };
+
+    let known = [
+        check_section::<Movement>(report, &ini),
+        check_section::<FileBrowserAction>(report, &ini),
+        check_section::<FileListAction>(report, &ini),
+        check_section::<TabAction>(report, &ini),
+        check_section::<MediaAction>(report, &ini),
+        check_section::<BookmarkAction>(report, &ini),
+        check_section::<ProcessAction>(report, &ini),
+        check_section::<MiniBufferAction>(report, &ini),
+        check_section::<FoldAction>(report, &ini),
+        check_section::<LogAction>(report, &ini),
+        check_section::<QuickActionAction>(report, &ini),
+        check_section::<BasketAction>(report, &ini),
+        check_section::<TagAction>(report, &ini),
+        check_section::<TaggedAction>(report, &ini),
+        check_section::<JumpListAction>(report, &ini),
+        check_section::<CompletionAction>(report, &ini),
+        check_section::<HistorySearchAction>(report, &ini),
+        check_section::<PaletteAction>(report, &ini),
+        "commands",
+    ];
+    check_commands(report, &ini);
+
+    let mut seen = vec![];
+
+    for (section, properties) in ini.iter() {
+        match section {
+            Some(section) if !known.contains(&section) => {
+                report.warning("keys", format!("Unknown section [{}] is ignored", section))
+            }
+            Some(section) if seen.contains(&section) => {
+                report.error("keys", format!("[{}] is there twice, only the first one counts", section))
+            }
+            Some(section) => seen.push(section),
+            None if properties.iter().next().is_some() => {
+                report.warning("keys", String::from("Bindings before the first section are ignored"))
+            }
+            _ => {}
+        }
+    }
+
+    // Sections left out fall back to the defaults, just like in wandex
+    let keybinds = KeyBinds::load()?;
+    check_shadowed(report, &keybinds);
+
+    Ok(())
+}
+
+// Everything in there gets run, so it has to be executable
+fn check_tree(report: &mut Report, name: &str, dir: &Path) -> WResult<()> {
+    if !dir.exists() {
+        report.warning(name, String::from("Not found, the defaults are installed on the next start"));
+        return Ok(());
+    }
+
+    let mut entries = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        let place = format!("{}/{}", name, path.file_name().unwrap_or_default().to_string_lossy());
+
+        match std::fs::metadata(&path) {
+            Ok(meta) if meta.is_dir() => check_tree(report, &place, &path)?,
+            Ok(meta) if meta.permissions().mode() & 0o111 == 0 => {
+                report.error(&place, String::from("Not executable"))
+            }
+            Ok(_) => {}
+            Err(_) => {
+                let target = std::fs::read_link(&path).unwrap_or_default();
+                report.error(&place, format!("Points to {}, which doesn't exist",
+                                             target.to_string_lossy()))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_check_config() {
+    let mut report = Report { errors: 0, warnings: 0 };
+    let text = "# comment\n\n  # indented\nanimation=on\nanimation=off\nfoo=1\nratios=1,x\nnothing\n";
+    check_config_text(&mut report, text);
+    assert_eq!((report.errors, report.warnings), (3, 1));
+
+    let mut report = Report { errors: 0, warnings: 0 };
+    check_config_text(&mut report, "parent_columns=2\n");
+    assert_eq!((report.errors, report.warnings), (1, 0));
+
+    let mut report = Report { errors: 0, warnings: 0 };
+    check_config_text(&mut report, "parent_columns=2\nratios=1,2,3,4\n");
+    assert_eq!((report.errors, report.warnings), (0, 0));
+}
+
+#[test]
+fn test_check_bindings() {
+    let keys = |keys| parse_keys(keys).unwrap();
+    let mut report = Report { errors: 0, warnings: 0 };
+
+    check_bindings(&mut report, "test", vec![
+        (String::from("Quit"), keys("q")),
+        (String::from("Quit"), keys("q")),
+        (String::from("Up"), keys("g k")),
+        (String::from("Down"), keys("g k")),
+        (String::from("Left"), keys("h")),
+    ]);
+    assert_eq!((report.errors, report.warnings), (1, 1));
+}
+
+#[test]
+fn test_covers() {
+    use termion::event::Key;
+    let (g, h) = (AnyKey::Key(Key::Char('g')), AnyKey::Key(Key::Char('h')));
+
+    assert!(covers(&[g], &[g, h]));
+    assert!(covers(&[AnyKey::AnyChar], &[h]));
+    assert!(!covers(&[g, h], &[g]));
+    assert!(!covers(&[g], &[h]));
+    assert!(!covers(&[h], &[AnyKey::AnyChar]));
+    assert!(!covers(&[AnyKey::AnyF], &[h]));
+}
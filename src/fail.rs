@@ -97,6 +97,10 @@ pub enum WError {
     KeyBind(KeyBindError),
     #[error("FileBrowser needs to know about all tab's files to run exec!")]
     FileBrowserNeedTabFiles,
+    #[error("{} has to be run by the tabs", _0)]
+    TabActionFromPalette(crate::keybind::TabAction),
+    #[error("Command {} needs the files of all tabs", _0)]
+    FileBrowserRunCommand(usize),
     #[error("{}", _0)]
     FileError(crate::files::FileError),
     #[error("{}", _0)]
@@ -428,6 +432,10 @@ pub enum KeyBindError {
     WrongAction(String),
     #[error("Failed to parse keybind: {}", _0)]
     ParseKeyError(String),
+    #[error("Defined keybind without a command: {}", _0)]
+    WrongCommand(String),
+    #[error("Refresh only works with fg or wait, background commands are left to the file watcher: {}", _0)]
+    RefreshInBackground(String),
     #[error("Trouble with ini file! Error: {}", _0)]
     IniError(Arc<ini::Error>),
     #[error("Couldn't parse as either char or u8: {}", _0)]
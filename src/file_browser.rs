@@ -6,6 +6,7 @@ use osstrtools::OsStrTools;
 use async_value::Stale;
 
 use std::io::Write;
+use std::fmt::Debug;
 use std::sync::Arc;
 use std::path::PathBuf;
 use std::ffi::OsString;
@@ -23,11 +24,19 @@ use crate::textview::TextView;
 use crate::fail::{WResult, WError, ErrorLog};
 use crate::widget::{Events, WidgetCore};
 use crate::proclist::ProcView;
-use crate::bookmarks::BMPopup;
+use crate::bookmarks::{Bookmark, BMPopup};
+use crate::basket::{Basket, BasketView};
+use crate::tagged::{Tagged, TaggedView};
+use crate::pathlist::{FilePick, PathList, PathListView};
+use crate::jumplist::{JumpList, JumpListView};
+use crate::palette::{Command, PaletteView};
+use crate::cmdline::{BookmarkCommand, ExCommand, Selection, TabCommand};
+use crate::session::{Session, TabSession};
 use crate::term;
 use crate::term::ScreenExt;
 use crate::foldview::LogView;
 use crate::coordinates::Coordinates;
+use crate::config::Layout;
 use crate::dirty::Dirtyable;
 use crate::stats::{FsStat, FsExt};
 
@@ -84,10 +93,35 @@ impl Widget for FileBrowserWidgets {
     }
 }
 
+// How much fits on the screen, see config.breakpoints
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Breakpoint {
+    Wide,
+    Narrow,
+    Tall,
+    Single,
+}
+
 pub struct FileBrowser {
     pub columns: HBox<FileBrowserWidgets>,
     pub cwd: File,
     pub prev_cwd: Option<File>,
+    // For Back and Forward, every tab has its own
+    jumps: JumpList,
+    // Number of columns left of the main column, nearest parent last
+    pub parent_columns: usize,
+    pub layout: Layout,
+    // Index of the active pane in the dual pane layout, 0 is on the left
+    pub active_pane: usize,
+    // Directory of the inactive pane, kept around in the miller layout
+    pub other_cwd: Option<File>,
+    pub pane_preview: bool,
+    // Shown in the tab bar instead of the directory name
+    pub tab_name: Option<String>,
+    // Pinned tabs can't be closed
+    pub pinned: bool,
+    // Waiting for the tag label after TagWith and friends
+    tag_prefix: Option<FileBrowserAction>,
     core: WidgetCore,
     proc_view: Arc<Mutex<ProcView>>,
     bookmarks: Arc<Mutex<BMPopup>>,
@@ -100,21 +134,7 @@ impl Tabbable for TabView<FileBrowser> {
     type Tab = FileBrowser;
 
     fn new_tab(&mut self) -> WResult<()> {
-        self.active_tab_mut().save_tab_settings().log();
-
-        let cur_tab = self.active_tab();
-        let settings = cur_tab.fs_cache.tab_settings.read()?.clone();
-        let cache = cur_tab.fs_cache.new_client(settings).ok();
-
-        let mut tab = FileBrowser::new(&self.active_tab_().core, cache)?;
-
-        let proc_view = cur_tab.proc_view.clone();
-        let bookmarks = cur_tab.bookmarks.clone();
-        let log_view  = cur_tab.log_view.clone();
-        tab.proc_view = proc_view;
-        tab.bookmarks = bookmarks;
-        tab.log_view  = log_view;
-        tab.fs_stat = cur_tab.fs_stat.clone();
+        let tab = self.clone_tab()?;
 
         self.push_widget(tab)?;
         self.active = self.widgets.len() - 1;
@@ -122,10 +142,51 @@ impl Tabbable for TabView<FileBrowser> {
     }
 
     fn close_tab(&mut self) -> WResult<()> {
+        if self.active_tab().pinned {
+            return self.core.show_status("Can't close pinned tab!");
+        }
+
         self.close_tab_().log();
         Ok(())
     }
 
+    fn rename_tab(&mut self) -> WResult<()> {
+        use crate::minibuffer::MiniBufferEvent::Empty;
+
+        // An empty name goes back to the directory name
+        let name = match self.core.minibuffer("tab name") {
+            Err(WError::MiniBufferEvent(Empty)) => None,
+            name => Some(name?)
+        };
+
+        self.active_tab_mut().tab_name = name;
+        Ok(())
+    }
+
+    fn duplicate_tab(&mut self) -> WResult<()> {
+        let mut tab = self.clone_tab()?;
+        let cur_tab = self.active_tab();
+
+        tab.set_dual_pane(cur_tab.layout == Layout::DualPane, cur_tab.other_cwd.clone());
+        if cur_tab.layout == Layout::DualPane {
+            tab.active_pane = cur_tab.active_pane;
+            tab.pane_preview = cur_tab.pane_preview;
+            tab.update_layout();
+        }
+        tab.tab_name = cur_tab.tab_name.clone();
+        tab.jumps = cur_tab.jumps.clone();
+
+        self.widgets.insert(self.active + 1, tab);
+        self.active += 1;
+        self.on_tab_switch()
+    }
+
+    fn toggle_pin_tab(&mut self) -> WResult<()> {
+        let tab = self.active_tab_mut();
+        tab.pinned = !tab.pinned;
+        Ok(())
+    }
+
     fn next_tab(&mut self) -> WResult<()> {
         self.next_tab_();
         Ok(())
@@ -142,10 +203,19 @@ impl Tabbable for TabView<FileBrowser> {
 
     fn get_tab_names(&self) -> Vec<Option<String>> {
         self.widgets.iter().map(|filebrowser| {
-            let path = filebrowser.cwd.path();
-            let last_dir = path.components().last().unwrap();
-            let dir_name = last_dir.as_os_str().to_string_lossy().to_string();
-            Some(dir_name)
+            let name = match &filebrowser.tab_name {
+                Some(name) => name.clone(),
+                None => {
+                    let path = filebrowser.cwd.path();
+                    let last_dir = path.components().next_back().unwrap();
+                    last_dir.as_os_str().to_string_lossy().to_string()
+                }
+            };
+
+            match filebrowser.pinned {
+                true => Some(format!("+{}", name)),
+                false => Some(name)
+            }
         }).collect()
     }
 
@@ -161,31 +231,78 @@ impl Tabbable for TabView<FileBrowser> {
         self.active_tab_mut().refresh()
     }
 
-    fn on_key_sub(&mut self, key: Key) -> WResult<()> {
-        match self.active_tab_mut().on_key(key) {
-            // returned by specific tab when called with ExecCmd action
-            Err(WError::FileBrowserNeedTabFiles) => {
-                let tab_dirs = self.widgets.iter().map(|w| w.cwd.clone())
-                    .collect::<Vec<_>>();
-                let selected_files = self
-                    .widgets
-                    .iter()
-                    .map(|w| {
-                        w.selected_files().unwrap_or(vec![])
-                    }).collect();
+    fn captures_key(&self, key: Key) -> bool {
+        self.active_tab().captures_key(key)
+    }
 
-                self.widgets[self.active].exec_cmd(tab_dirs, selected_files)
+    fn save_session(&mut self) -> WResult<()> {
+        use crate::minibuffer::MiniBufferEvent::Empty;
+
+        let name = match self.core.minibuffer("session") {
+            Err(WError::MiniBufferEvent(Empty)) => crate::session::name(),
+            name => name?
+        };
+
+        self.save_session_as(&name)?;
+        crate::session::set_name(&name)?;
+        self.core.show_status(&format!("Saved session: {}", name))
+    }
+
+    fn load_session(&mut self) -> WResult<()> {
+        let name = self.core.minibuffer("session")?;
+        let session = Session::load(&name)?;
+
+        // Don't lose the current tabs
+        self.save_session_as(&crate::session::name()).log();
+
+        self.restore_session(session)?;
+        crate::session::set_name(&name)?;
+        self.core.show_status(&format!("Loaded session: {}", name))
+    }
+
+    fn list_sessions(&mut self) -> WResult<()> {
+        let sessions = crate::session::list()?;
+
+        let status = match sessions.len() {
+            0 => String::from("No saved sessions"),
+            _ => format!("Sessions: {}", sessions.join(", "))
+        };
+        self.core.show_status(&status)
+    }
+
+    fn command_line(&mut self) -> WResult<()> {
+        let line = self.core.minibuffer("command")?;
+
+        let result = match crate::cmdline::parse(&line)? {
+            ExCommand::Tab(tab) => self.tab_command(tab),
+            // Every tab shares the config
+            ExCommand::Set(name, value) => {
+                self.core.set_config(&name, &value)?;
+                self.on_config_loaded()?;
+                self.core.show_status(&format!("Set {} to {}", name, value))
             }
-            result @ _ => result
-        }
+            command => self.active_tab_mut().run_ex_command(command),
+        };
+
+        self.tab_result(result)
+    }
+
+    fn on_key_sub(&mut self, key: Key) -> WResult<()> {
+        let result = self.active_tab_mut().on_key(key);
+        self.tab_result(result)
     }
 
     fn on_refresh(&mut self) -> WResult<()> {
         let open_dirs = self.widgets
             .iter()
             .fold(HashSet::new(), |mut dirs, tab| {
-                tab.left_dir().map(|dir| dirs.insert(dir.clone())).ok();
+                for dir in tab.parent_dirs() {
+                    dirs.insert(dir.clone());
+                }
                 dirs.insert(tab.cwd.clone());
+                if tab.layout == Layout::DualPane {
+                    tab.other_cwd.as_ref().map(|dir| dirs.insert(dir.clone()));
+                }
                 tab.preview_widget()
                     .map(|preview| preview.get_file().map(|file| {
                         if file.is_dir() {
@@ -210,8 +327,28 @@ impl Tabbable for TabView<FileBrowser> {
     fn on_config_loaded(&mut self) -> WResult<()> {
         let show_hidden = self.core.config().show_hidden();
 
+        let parent_columns = self.core.config().parent_columns;
+
         for tab in self.widgets.iter_mut() {
-            tab.left_async_widget_mut().map(|async_w| {
+            tab.set_parent_columns(parent_columns);
+
+            for depth in 1..=tab.parent_columns {
+                tab.parent_async_widget_mut(depth).map(|async_w| {
+                    async_w.widget.on_ready(move |mut w, _| {
+                        w.as_mut()
+                         .map(|w| {
+                             if w.content.show_hidden != show_hidden {
+                                 w.content.show_hidden = show_hidden;
+                                 w.content.recalculate_len();
+                                 w.refresh().log();
+                             }
+                         }).ok();
+                        Ok(())
+                    }).log();
+                }).log();
+            }
+
+            tab.main_async_widget_mut().map(|async_w| {
                 async_w.widget.on_ready(move |mut w, _| {
                     w.as_mut()
                      .map(|w| {
@@ -222,10 +359,10 @@ impl Tabbable for TabView<FileBrowser> {
                          }
                      }).ok();
                     Ok(())
-                }).log();
+                }).log()
             }).log();
 
-            tab.main_async_widget_mut().map(|async_w| {
+            tab.other_async_widget_mut().map(|async_w| {
                 async_w.widget.on_ready(move |mut w, _| {
                     w.as_mut()
                      .map(|w| {
@@ -237,10 +374,10 @@ impl Tabbable for TabView<FileBrowser> {
                      }).ok();
                     Ok(())
                 }).log()
-            }).log();
+            }).ok();
 
             tab.preview_widget_mut().map(|w| w.config_loaded()).ok();
-            tab.columns.set_ratios(self.core.config().ratios);
+            tab.update_layout();
         }
         Ok(())
     }
@@ -253,29 +390,203 @@ impl Tabbable for TabView<FileBrowser> {
 
 
 
+impl TabView<FileBrowser> {
+
+    // Some results of the active tab need the other tabs to finish
+    fn tab_result(&mut self, result: WResult<()>) -> WResult<()> {
+        match result {
+            // returned by specific tab when called with ExecCmd action
+            Err(WError::FileBrowserNeedTabFiles) => {
+                let (tab_dirs, selected_files) = self.tab_files();
+                self.widgets[self.active].exec_cmd(tab_dirs, selected_files)
+            }
+            Err(WError::FileBrowserRunCommand(n)) => {
+                let (tab_dirs, selected_files) = self.tab_files();
+                self.widgets[self.active].run_user_command(n, tab_dirs, selected_files)
+            }
+            Err(WError::TabActionFromPalette(action)) => self.do_action(&action),
+            result => result
+        }
+    }
+
+    // For the "$0", "$0s", ... patterns
+    fn tab_files(&self) -> (Vec<File>, Vec<Vec<File>>) {
+        let tab_dirs = self.widgets.iter().map(|w| w.cwd.clone())
+            .collect::<Vec<_>>();
+        let selected_files = self
+            .widgets
+            .iter()
+            .map(|w| {
+                w.selected_files().unwrap_or(vec![])
+            }).collect();
+
+        (tab_dirs, selected_files)
+    }
+
+    fn tab_command(&mut self, tab: TabCommand) -> WResult<()> {
+        match tab {
+            TabCommand::New(path) => {
+                self.new_tab()?;
+                match path {
+                    Some(path) => self.active_tab_mut().run_ex_command(ExCommand::Cd(Some(path))),
+                    None => Ok(())
+                }
+            }
+            TabCommand::Close => self.close_tab(),
+            TabCommand::Next => self.next_tab(),
+            TabCommand::Prev => self.prev_tab(),
+            TabCommand::Goto(n) => self.goto_tab(n.saturating_sub(1)),
+            TabCommand::Rename(name) => {
+                self.active_tab_mut().tab_name = name;
+                Ok(())
+            }
+            TabCommand::Duplicate => self.duplicate_tab(),
+            TabCommand::Pin => self.toggle_pin_tab(),
+        }
+    }
+
+    // A fresh tab in the current directory, sharing everything that's
+    // shared between tabs with the active one
+    fn clone_tab(&mut self) -> WResult<FileBrowser> {
+        self.active_tab_mut().save_tab_settings().log();
+
+        let cur_tab = self.active_tab();
+        let cache = cur_tab.fs_cache
+                           .new_client(cur_tab.fs_cache.tab_settings.read()?.clone())
+                           .ok();
+
+        let mut tab = FileBrowser::new(&self.active_tab_().core, cache)?;
+
+        let proc_view = cur_tab.proc_view.clone();
+        let bookmarks = cur_tab.bookmarks.clone();
+        let log_view  = cur_tab.log_view.clone();
+        tab.proc_view = proc_view;
+        tab.bookmarks = bookmarks;
+        tab.log_view  = log_view;
+        tab.fs_stat = cur_tab.fs_stat.clone();
+
+        Ok(tab)
+    }
+
+    fn session(&mut self) -> Session {
+        let tabs = self.widgets.iter_mut().map(|tab| {
+            tab.save_tab_settings().log();
+
+            TabSession {
+                cwd: tab.cwd.clone(),
+                layout: tab.layout,
+                other_cwd: tab.other_cwd.clone(),
+                name: tab.tab_name.clone(),
+                pinned: tab.pinned,
+                settings: tab.fs_cache
+                             .tab_settings
+                             .read()
+                             .map(|settings| settings.clone())
+                             .unwrap_or_default()
+            }
+        }).collect();
+
+        Session { active: self.active, tabs }
+    }
+
+    pub fn save_session_as(&mut self, name: &str) -> WResult<()> {
+        self.session().save(name)
+    }
+
+    pub fn restore_session(&mut self, session: Session) -> WResult<()> {
+        let cache = match self.widgets.first() {
+            Some(tab) => tab.fs_cache.clone(),
+            None => FsCache::new(self.core.get_sender())
+        };
+
+        let mut tabs = vec![];
+
+        for tab_session in session.tabs {
+            let cwd = tab_session.cwd.path;
+            if !cwd.is_dir() {
+                let msg = format!("Can't restore tab, {} is gone", cwd.to_string_lossy());
+                Err::<(), _>(WError::Log(msg)).log();
+                continue;
+            }
+
+            let tab_cache = cache.new_client(tab_session.settings)?;
+            let mut tab = FileBrowser::new_in(&self.core, Some(tab_cache), cwd)?;
+
+            tab.set_dual_pane(tab_session.layout == Layout::DualPane, tab_session.other_cwd);
+            tab.tab_name = tab_session.name;
+            tab.pinned = tab_session.pinned;
+
+            tabs.push(tab);
+        }
+
+        // Keep sharing processes, bookmarks and the log between tabs
+        let (proc_view, bookmarks, log_view, fs_stat) =
+            match self.widgets.get(self.active).or(tabs.first()) {
+                Some(tab) => (tab.proc_view.clone(),
+                              tab.bookmarks.clone(),
+                              tab.log_view.clone(),
+                              tab.fs_stat.clone()),
+                None => return WError::no_widget()
+            };
+
+        for tab in tabs.iter_mut() {
+            tab.proc_view = proc_view.clone();
+            tab.bookmarks = bookmarks.clone();
+            tab.log_view = log_view.clone();
+            tab.fs_stat = fs_stat.clone();
+        }
+
+        self.active = session.active.min(tabs.len().saturating_sub(1));
+        self.widgets = tabs;
+        self.core.clear().log();
+        self.on_tab_switch()
+    }
+}
+
 impl FileBrowser {
     pub fn new(core: &WidgetCore, cache: Option<FsCache>) -> WResult<FileBrowser> {
+        let cwd = std::env::current_dir()?;
+        FileBrowser::new_in(core, cache, cwd)
+    }
+
+    pub fn new_in(core: &WidgetCore,
+                  cache: Option<FsCache>,
+                  cwd: PathBuf) -> WResult<FileBrowser> {
         let fs_cache = cache.unwrap_or_else(|| FsCache::new(core.get_sender()));
 
-        let cwd = std::env::current_dir().unwrap();
+        // The config is loaded in the background, so both have to come
+        // from the same one or they might not fit together
+        let config = core.config();
+        let parent_columns = config.parent_columns;
         let mut core_m = core.clone();
-        let mut core_l = core.clone();
         let mut core_p = core.clone();
 
         let mut columns = HBox::new(core);
-        columns.set_ratios(core.config().ratios);
+        columns.set_ratios(config.ratios);
         let list_coords = columns.calculate_coordinates()?;
 
-        core_l.coordinates = list_coords[0].clone();
-        core_m.coordinates = list_coords[1].clone();
-        core_p.coordinates = list_coords[2].clone();
+        core_m.coordinates = list_coords[parent_columns].clone();
+        core_p.coordinates = list_coords[parent_columns + 1].clone();
 
         let main_path = cwd.ancestors()
                            .take(1)
                            .map(|path| {
                                std::path::PathBuf::from(path)
                            }).last().ok_or(WError::NoneError)?;
-        let left_path = main_path.parent().map(|p| p.to_path_buf());
+
+        // Outermost parent first
+        for depth in (1..=parent_columns).rev() {
+            let mut core_l = core.clone();
+            core_l.coordinates = list_coords[parent_columns - depth].clone();
+
+            let left_dir = main_path.ancestors()
+                                    .nth(depth)
+                                    .and_then(|path| File::new_from_path(path).ok());
+            let left_widget = FileBrowser::new_parent_widget(&core_l,
+                                                             left_dir,
+                                                             fs_cache.clone());
+            columns.push_widget(FileBrowserWidgets::FileList(left_widget));
+        }
 
         let cache = fs_cache.clone();
         let main_widget = AsyncWidget::new(&core, move |stale| {
@@ -287,44 +598,16 @@ impl FileBrowser {
                 .build()
         });
 
-        let cache = fs_cache.clone();
-        if let Some(left_path) = left_path {
-            let left_widget = AsyncWidget::new(&core_l.clone(), move |stale| {
-                let dir = File::new_from_path(&left_path)?;
-                let source = FileSource::Path(dir);
-                ListView::builder(core_l, source)
-                    .with_cache(cache)
-                    .with_stale(stale.clone())
-                    .build()
-            });
-            let left_widget = FileBrowserWidgets::FileList(left_widget);
-            columns.push_widget(left_widget);
-        } else {
-            let mut left_widget = AsyncWidget::new(&core_l.clone(), move |_| {
-                let files = Files::default();
-                let source = FileSource::Files(files);
-                ListView::builder(core_l, source).build()
-            });
-
-            left_widget.widget.on_ready(move |_, stale| {
-                // To stop from drawing empty placeholder
-                stale.set_stale()?;
-                Ok(())
-            }).log();
-
-            let left_widget = FileBrowserWidgets::FileList(left_widget);
-            columns.push_widget(left_widget);
-        }
-
         let previewer = Previewer::new(&core_p, fs_cache.clone());
 
         columns.push_widget(FileBrowserWidgets::FileList(main_widget));
         columns.push_widget(FileBrowserWidgets::Previewer(previewer));
-        columns.set_active(1).log();
+        columns.set_active(parent_columns).log();
         columns.refresh().log();
 
 
         let cwd = File::new_from_path(&cwd).unwrap();
+        let jumps = JumpList::new(&cwd);
 
         let proc_view = ProcView::new(&core);
         let bookmarks = BMPopup::new(&core);
@@ -333,15 +616,86 @@ impl FileBrowser {
 
 
 
-        Ok(FileBrowser { columns: columns,
-                         cwd: cwd,
-                         prev_cwd: None,
-                         core: core.clone(),
-                         proc_view: Arc::new(Mutex::new(proc_view)),
-                         bookmarks: Arc::new(Mutex::new(bookmarks)),
-                         log_view: Arc::new(Mutex::new(log_view)),
-                         fs_cache: fs_cache,
-                         fs_stat: Arc::new(RwLock::new(fs_stat)) })
+        let mut browser = FileBrowser { columns,
+                                        cwd,
+                                        prev_cwd: None,
+                                        jumps,
+                                        parent_columns,
+                                        layout: Layout::Miller,
+                                        active_pane: 0,
+                                        other_cwd: None,
+                                        pane_preview: false,
+                                        tab_name: None,
+                                        pinned: false,
+                                        tag_prefix: None,
+                                        core: core.clone(),
+                                        proc_view: Arc::new(Mutex::new(proc_view)),
+                                        bookmarks: Arc::new(Mutex::new(bookmarks)),
+                                        log_view: Arc::new(Mutex::new(log_view)),
+                                        fs_cache,
+                                        fs_stat: Arc::new(RwLock::new(fs_stat)) };
+
+        if core.config().layout == Layout::DualPane {
+            browser.open_other_pane();
+        }
+
+        Ok(browser)
+    }
+
+    fn new_parent_widget(core: &WidgetCore,
+                         dir: Option<File>,
+                         cache: FsCache) -> AsyncWidget<ListView<Files>> {
+        let core_l = core.clone();
+
+        match dir {
+            Some(dir) => AsyncWidget::new(core, move |stale| {
+                let source = FileSource::Path(dir);
+                ListView::builder(core_l, source)
+                    .with_cache(cache)
+                    .with_stale(stale.clone())
+                    .build()
+            }),
+            None => {
+                let mut left_widget = AsyncWidget::new(core, move |_| {
+                    let files = Files::default();
+                    let source = FileSource::Files(files);
+                    ListView::builder(core_l, source).build()
+                });
+
+                left_widget.widget.on_ready(move |_, stale| {
+                    // To stop from drawing empty placeholder
+                    stale.set_stale()?;
+                    Ok(())
+                }).log();
+
+                left_widget
+            }
+        }
+    }
+
+    fn set_parent_columns(&mut self, count: usize) {
+        if count == self.parent_columns { return; }
+
+        let active = self.columns
+                         .active
+                         .map(|active| active - self.parent_columns + count);
+
+        for _ in 0..self.parent_columns {
+            self.columns.remove_widget(0);
+        }
+
+        let mut dir = self.cwd.parent_as_file().ok();
+        for _ in 0..count {
+            let left_widget = FileBrowser::new_parent_widget(&self.core,
+                                                             dir.clone(),
+                                                             self.fs_cache.clone());
+            self.columns.prepend_widget(FileBrowserWidgets::FileList(left_widget));
+            dir = dir.and_then(|dir| dir.parent_as_file().ok());
+        }
+
+        self.parent_columns = count;
+        self.columns.active = active;
+        self.core.clear().log();
     }
 
     pub fn enter_dir(&mut self) -> WResult<()> {
@@ -362,11 +716,16 @@ impl FileBrowser {
             }
             self.preview_widget_mut()?.set_stale().log();
             self.preview_widget_mut()?.cancel_animation().log();
-            let previewer_files = self.preview_widget_mut()?.take_files().ok();
+            // A hidden preview might still show some other directory
+            let previewer_files = match self.preview_widget()?.get_file() {
+                Some(preview_file) if preview_file == &dir => {
+                    self.preview_widget_mut()?.take_files().ok()
+                }
+                _ => None
+            };
             let main_files = self.take_main_files().ok();
 
-            self.prev_cwd = Some(self.cwd.clone());
-            self.cwd = dir.clone();
+            self.change_cwd(dir.clone());
 
             let cache = self.fs_cache.clone();
             self.main_async_widget_mut()?.change_to(move |stale, core| {
@@ -384,17 +743,21 @@ impl FileBrowser {
 
             let cache = self.fs_cache.clone();
             let left_dir = self.cwd.parent_as_file()?;
-            self.left_async_widget_mut()?.change_to(move |stale, core| {
-                let source = match main_files {
-                    Some(files) => FileSource::Files(files),
-                    None => FileSource::Path(left_dir)
-                };
+            if let Ok(left_async_widget) = self.left_async_widget_mut() {
+                left_async_widget.change_to(move |stale, core| {
+                    let source = match main_files {
+                        Some(files) => FileSource::Files(files),
+                        None => FileSource::Path(left_dir)
+                    };
 
-                ListView::builder(core, source)
-                    .with_cache(cache)
-                    .with_stale(stale.clone())
-                    .build()
+                    ListView::builder(core, source)
+                        .with_cache(cache)
+                        .with_stale(stale.clone())
+                        .build()
                 }).log();
+            }
+
+            self.parent_columns_goto(2).log();
         } else {
             self.preview_widget_mut().map(|preview| {
                 preview.cancel_animation().log();
@@ -424,33 +787,52 @@ impl FileBrowser {
         Ok(())
     }
 
-    pub fn move_down_left_widget(&mut self) -> WResult<()> {
-        let left_files_pos = self.left_widget()?.get_selection();
+    // Finds the next or previous directory next to the current one
+    fn sibling_dir(&self, forward: bool) -> WResult<File> {
+        let parent = self.cwd.parent_as_file()?;
 
-        let next_dir = self.get_left_files()?
-            .iter_files()
-            .skip(left_files_pos + 1)
-            .find(|&file| file.is_dir())
-            .cloned();
+        // Without a parent column the listing has to come from the cache
+        let cached_files;
+        let files = match self.get_left_files() {
+            Ok(files) if files.directory == parent => files,
+            _ => {
+                cached_files = self.fs_cache.get_files_sync(&parent)?;
+                &cached_files
+            }
+        };
 
-        self.main_widget_goto(&next_dir.ok_or(WError::NoneError)?).log();
+        let pos = files.iter_files()
+                       .position(|file| file.path == self.cwd.path)
+                       .ok_or(WError::NoneError)?;
 
-        Ok(())
+        let next_dir = if forward {
+            files.iter_files()
+                 .skip(pos + 1)
+                 .find(|&file| file.is_dir())
+                 .cloned()
+        } else {
+            files.iter_files()
+                 .take(pos)
+                 .collect::<Vec<&File>>()
+                 .into_iter()
+                 .rev()
+                 .find(|&file| file.is_dir())
+                 .cloned()
+        };
+
+        next_dir.ok_or(WError::NoneError)
     }
 
-    pub fn move_up_left_widget(&mut self) -> WResult<()> {
-        let left_files_pos = self.left_widget()?.get_selection();
+    pub fn move_down_left_widget(&mut self) -> WResult<()> {
+        let next_dir = self.sibling_dir(true)?;
+        self.main_widget_goto(&next_dir).log();
 
-        let next_dir = self.get_left_files()?
-            .iter_files()
-            .take(left_files_pos)
-            .collect::<Vec<&File>>()
-            .into_iter()
-            .rev()
-            .find(|&file| file.is_dir())
-            .cloned();
+        Ok(())
+    }
 
-        self.main_widget_goto(&next_dir.ok_or(WError::NoneError)?).log();
+    pub fn move_up_left_widget(&mut self) -> WResult<()> {
+        let next_dir = self.sibling_dir(false)?;
+        self.main_widget_goto(&next_dir).log();
 
         Ok(())
     }
@@ -465,9 +847,12 @@ impl FileBrowser {
             args: None,
             vars: None,
             cwd: cwd.clone(),
+            cwd_file: None,
+            prev_cwd: None,
             cwd_files: None,
             tab_files: None,
-            tab_paths: None
+            tab_paths: None,
+            input: None
         };
 
         self.proc_view
@@ -495,11 +880,21 @@ impl FileBrowser {
             .map(|p| p.set_stale())
             .ok();
 
-        let dir = dir.clone();
-        let cache = self.fs_cache.clone();
+        self.change_cwd(dir.clone());
+        self.load_main_widget()?;
+        self.parent_columns_goto(1).log();
 
-        self.prev_cwd = Some(self.cwd.clone());
-        self.cwd = dir.clone();
+        Ok(())
+    }
+
+    // Reads the current directory again, the selection is kept
+    fn reload_main_widget(&mut self) -> WResult<()> {
+        self.save_tab_settings()?;
+        self.load_main_widget()
+    }
+
+    fn load_main_widget(&mut self) -> WResult<()> {
+        let cache = self.fs_cache.clone();
         let file_source = FileSource::Path(self.cwd.clone());
 
         let main_async_widget = self.main_async_widget_mut()?;
@@ -512,31 +907,56 @@ impl FileBrowser {
             Ok(view)
         }).log();
 
+        Ok(())
+    }
 
-        if let Ok(grand_parent) = self.cwd()?.parent_as_file() {
-            self.left_widget_goto(&grand_parent).log();
-        } else {
-            self.left_async_widget_mut()?.change_to(move |_,_| {
-                WError::stale()?
-            }).log();
-        }
-
+    // Points the parent columns from the given depth on at the
+    // ancestors of the current directory
+    pub fn parent_columns_goto(&mut self, from_depth: usize) -> WResult<()> {
+        let mut dir = self.cwd.parent_as_file().ok();
 
+        for depth in 1..=self.parent_columns {
+            if depth >= from_depth {
+                self.parent_widget_goto(depth, dir.as_ref()).log();
+            }
+            dir = dir.and_then(|dir| dir.parent_as_file().ok());
+        }
 
         Ok(())
     }
 
-    pub fn left_widget_goto(&mut self, dir: &File) -> WResult<()> {
+    pub fn parent_widget_goto(&mut self, depth: usize, dir: Option<&File>) -> WResult<()> {
+        let dir = match dir {
+            Some(dir) => dir,
+            None => {
+                // Just place a dummy in this column
+                self.parent_async_widget_mut(depth)?.change_to(move |_, core| {
+                    let files = Files::default();
+                    let source = FileSource::Files(files);
+                    ListView::builder(core, source).build()
+                }).log();
+
+                self.parent_async_widget_mut(depth)?.widget.on_ready(move |_, stale| {
+                    // To stop from drawing empty placeholder
+                    stale.set_stale()?;
+                    Ok(())
+                }).log();
+
+                return Ok(());
+            }
+        };
+
         // Check if we're in the correct directory already and return
         // if we are
-        let left_dir = &self.left_widget()?.content.directory;
-        if self.left_widget().is_ok() && left_dir == dir {
-            return Ok(());
+        if let Ok(left_widget) = self.parent_widget(depth) {
+            if &left_widget.content.directory == dir {
+                return Ok(());
+            }
         }
 
         let cache = self.fs_cache.clone();
         let file_source = FileSource::Path(dir.clone());
-        let left_async_widget = self.left_async_widget_mut()?;
+        let left_async_widget = self.parent_async_widget_mut(depth)?;
         left_async_widget.change_to(move |stale, core| {
             let view = ListView::builder(core, file_source)
                 .with_cache(cache)
@@ -555,8 +975,7 @@ impl FileBrowser {
             let main_selection = self.cwd.clone();
             let preview_files = self.take_main_files();
 
-            self.prev_cwd = Some(self.cwd.clone());
-            self.cwd = new_cwd.clone();
+            self.change_cwd(new_cwd.clone());
 
             let cache = self.fs_cache.clone();
 
@@ -574,29 +993,7 @@ impl FileBrowser {
                     .build()
             }).log();
 
-            if let Ok(left_dir) = new_cwd.parent_as_file() {
-                let file_source = FileSource::Path(left_dir);
-                let cache = self.fs_cache.clone();
-                self.left_async_widget_mut()?.change_to(move |stale, core| {
-                    ListView::builder(core, file_source)
-                        .with_cache(cache)
-                        .with_stale(stale.clone())
-                        .build()
-                }).log();
-            } else {
-                // Just place a dummy in the left column
-                self.left_async_widget_mut()?.change_to(move |_, core| {
-                    let files = Files::default();
-                    let source = FileSource::Files(files);
-                    ListView::builder(core, source).build()
-                }).log();
-
-                self.left_async_widget_mut()?.widget.on_ready(move |_, stale| {
-                    // To stop from drawing empty placeholder
-                    stale.set_stale()?;
-                    Ok(())
-                }).log()
-            }
+            self.parent_columns_goto(1).log();
 
 
             if let Ok(preview_files) = preview_files {
@@ -611,6 +1008,194 @@ impl FileBrowser {
         self.refresh()
     }
 
+    // Every directory change goes through here
+    fn change_cwd(&mut self, dir: File) {
+        let selection = self.fs_cache.get_selection(&self.cwd).ok();
+        self.jumps.visit(&dir, selection);
+        crate::frecency::visit(&dir.path);
+
+        self.prev_cwd = Some(std::mem::replace(&mut self.cwd, dir));
+    }
+
+    pub fn go_back_in_history(&mut self) -> WResult<()> {
+        let selection = self.fs_cache.get_selection(&self.cwd).ok();
+        match self.jumps.back(selection) {
+            Some(jump) => self.goto_jump(jump.dir, jump.selection),
+            None => self.core.show_status("Already at the oldest location")
+        }
+    }
+
+    pub fn go_forward_in_history(&mut self) -> WResult<()> {
+        let selection = self.fs_cache.get_selection(&self.cwd).ok();
+        match self.jumps.forward(selection) {
+            Some(jump) => self.goto_jump(jump.dir, jump.selection),
+            None => self.core.show_status("Already at the newest location")
+        }
+    }
+
+    fn goto_jump(&mut self, dir: File, selection: Option<File>) -> WResult<()> {
+        if let Some(selection) = selection {
+            self.fs_cache.set_selection(dir.clone(), selection).log();
+        }
+        self.main_widget_goto(&dir)
+    }
+
+    pub fn show_jump_list(&mut self) -> WResult<()> {
+        self.save_selected_file().log();
+
+        let mut view = JumpListView::new(&self.core, self.fs_cache.clone(), self.jumps.clone())?;
+
+        match self.run_list_popup(&mut view)? {
+            Some(pos) => {
+                let selection = self.fs_cache.get_selection(&self.cwd).ok();
+                match self.jumps.jump_to(pos, selection) {
+                    Some(jump) => self.goto_jump(jump.dir, jump.selection),
+                    None => Ok(())
+                }
+            }
+            None => Ok(())
+        }
+    }
+
+    pub fn show_command_palette(&mut self) -> WResult<()> {
+        let palette = PaletteView::new(&self.core);
+        self.run_palette(palette)
+    }
+
+    // The media keys only do something while media is previewed
+    pub fn show_help(&mut self) -> WResult<()> {
+        let mut sections = vec!["tabs", "commands", "filebrowser", "filelist", "movement"];
+        if self.preview_widget().map(|preview| preview.shows_media()).unwrap_or(false) {
+            sections.push("media");
+        }
+
+        let palette = PaletteView::help(&self.core, &sections);
+        self.run_palette(palette)
+    }
+
+    fn run_palette(&mut self, mut palette: PaletteView) -> WResult<()> {
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        loop {
+            match palette.pick() {
+                // Ignore refresh
+                Err(WError::RefreshParent) => continue,
+                Err(WError::TerminalResizedError) |
+                Err(WError::WidgetResizedError) => {
+                    self.resize().log();
+                    palette.set_coordinates(&self.core.coordinates).log();
+                }
+                Ok(Some(entry)) => return self.run_command(entry),
+                Ok(None) => return Ok(()),
+                Err(err) => return Err(err)
+            }
+        }
+    }
+
+    // Runs the action just like its key would
+    fn run_command(&mut self, entry: crate::palette::Entry) -> WResult<()> {
+        match entry.command {
+            Command::Movement(movement) => self.movement(&movement)?,
+            Command::FileBrowser(action) => self.do_action(&action)?,
+            Command::FileList(action) => {
+                self.main_widget_mut()?.do_action(&action)?;
+                self.save_tab_settings()?;
+            }
+            // Handled by the tabs, just like ExecCmd
+            Command::Tab(action) => Err(WError::TabActionFromPalette(action))?,
+            // The media player sits deep inside the preview
+            Command::Media(action) => {
+                let keys = self.core.config().keybinds.media.keys(&action);
+
+                let key = keys.iter().find_map(|keys| match keys.as_slice() {
+                    [AnyKey::Key(key)] => Some(*key),
+                    _ => None
+                });
+
+                match key {
+                    Some(key) => self.preview_widget_mut()?.on_key(key)?,
+                    _ => self.core.show_status(&format!("{} needs a key to work", entry.name))?
+                }
+            }
+            Command::Popup => {
+                self.core.show_status(&format!("{} only works in [{}]",
+                                               entry.name,
+                                               entry.section))?
+            }
+        }
+
+        if self.preview_visible() { self.update_preview().log(); }
+        Ok(())
+    }
+
+    // Tab commands and :set are done by the tabs
+    pub fn run_ex_command(&mut self, command: ExCommand) -> WResult<()> {
+        match command {
+            ExCommand::Cd(None) => self.go_home(),
+            ExCommand::Cd(Some(ref dir)) if dir == "-" => self.goto_prev_cwd(),
+            ExCommand::Cd(Some(path)) => {
+                let path = crate::cmdline::expand_path(&self.cwd.path, &path);
+                let file = File::new_from_path(&path)?;
+
+                // A file is selected in its directory
+                match path.is_dir() {
+                    true => self.main_widget_goto(&file),
+                    false if path.exists() => self.goto_file(&file),
+                    false => WError::log(&format!("No such directory: {}", path.display()))
+                }
+            }
+            ExCommand::Sort(sort, reverse) => {
+                self.main_widget_mut()?.set_sort(sort, reverse);
+                self.save_tab_settings()
+            }
+            ExCommand::Filter(filter) => {
+                self.main_widget_mut()?.set_filter(filter);
+                Ok(())
+            }
+            ExCommand::Select(selection) => {
+                let widget = self.main_widget_mut()?;
+
+                match selection {
+                    Selection::Glob(pattern) => {
+                        let count = widget.select_glob(&pattern);
+                        return self.core.show_status(&format!("Selected {} files", count));
+                    }
+                    Selection::All => { widget.select_glob("*"); }
+                    Selection::Nothing => widget.clear_selections(),
+                    Selection::Invert => widget.invert_selection(),
+                }
+                Ok(())
+            }
+            ExCommand::Bookmark(BookmarkCommand::Add(key, name)) => {
+                let cwd = self.cwd.path.to_string_lossy().to_string();
+                let mut bookmark = Bookmark::new(&key, &cwd);
+                bookmark.name = name;
+
+                self.bookmarks.lock().add(bookmark)?;
+                self.core.show_status(&format!("Bookmarked {} as {}", cwd, key))
+            }
+            ExCommand::Bookmark(BookmarkCommand::Delete(key)) => {
+                self.bookmarks.lock().remove(&key)?;
+                self.core.show_status(&format!("Deleted bookmark {}", key))
+            }
+            ExCommand::Bookmark(BookmarkCommand::Goto(key)) => {
+                let path = self.bookmarks.lock().path(&key)?;
+                self.main_widget_goto(&File::new_from_path(&PathBuf::from(path))?)
+            }
+            ExCommand::Action(name) => {
+                let entry = crate::palette::entries(&self.core.config().keybinds)
+                    .into_iter()
+                    .find(|entry| entry.name.eq_ignore_ascii_case(&name));
+
+                match entry {
+                    Some(entry) => self.run_command(entry),
+                    None => WError::log(&format!("Unknown command: {}", name))
+                }
+            }
+            ExCommand::Tab(_) | ExCommand::Set(..) => Ok(()),
+        }
+    }
+
     pub fn goto_prev_cwd(&mut self) -> WResult<()> {
         let prev_cwd = self.prev_cwd.take().ok_or(WError::NoneError)?;
         self.main_widget_goto(&prev_cwd)?;
@@ -676,18 +1261,43 @@ impl FileBrowser {
         Ok(())
     }
 
+    // Like z, jumps to the best match among often and recently visited dirs
+    pub fn jump_frecent(&mut self) -> WResult<()> {
+        let query = self.core.minibuffer("jump")?;
+
+        // Either picked from the list or typed and the best match is taken
+        let picked = PathBuf::from(&query);
+        if picked.is_absolute() && picked.is_dir() {
+            return self.main_widget_goto(&File::new_from_path(&picked)?);
+        }
+
+        match crate::frecency::query(&query)?.first() {
+            Some(path) => self.main_widget_goto(&File::new_from_path(path)?),
+            None => self.core.show_status(&format!("No match for {}", query))
+        }
+    }
+
     pub fn add_bookmark(&mut self) -> WResult<()> {
         let cwd = self.cwd.path.to_string_lossy().to_string();
+        let input = self.core.minibuffer("bookmark (key [name] [@group])")?;
+
+        let bookmark = match Bookmark::from_input(&input, &cwd) {
+            Some(bookmark) => bookmark,
+            None => return self.core.show_status("No key for bookmark!")
+        };
+        let key = bookmark.key.clone();
+
+        if key.contains(':') {
+            return self.core.show_status("Bookmark keys can't contain \":\"!");
+        }
+
         let coords = &self.core.coordinates;
+        let mut bookmarks = self.bookmarks.lock();
+        bookmarks.add(bookmark)?;
+        bookmarks.set_coordinates(coords)?;
+        drop(bookmarks);
 
-        self.bookmarks
-            .lock()
-            .set_coordinates(&coords)
-            .log();
-        self.bookmarks
-            .lock()
-            .add(&cwd)?;
-        Ok(())
+        self.core.show_status(&format!("Bookmarked {} as {}", cwd, key))
     }
 
     pub fn set_title(&self) -> WResult<()> {
@@ -722,27 +1332,36 @@ impl FileBrowser {
         Ok(())
     }
 
-    pub fn set_left_selection(&mut self) -> WResult<()> {
-        if self.cwd.parent().is_none() { return Ok(()) }
-        if !self.left_async_widget_mut()?.ready() { return Ok(()) }
+    pub fn set_parent_selections(&mut self) -> WResult<()> {
+        let mut selection = self.cwd.clone();
 
-        let selection = self.cwd()?.clone();
+        for depth in 1..=self.parent_columns {
+            if selection.parent().is_none() { break; }
+            self.set_parent_selection(depth, &selection).log();
+            selection = selection.parent_as_file()?;
+        }
+
+        Ok(())
+    }
+
+    fn set_parent_selection(&mut self, depth: usize, selection: &File) -> WResult<()> {
+        if !self.parent_async_widget_mut(depth)?.ready() { return Ok(()) }
 
         // Saves doing iteration to find file's position
-        if let Some(ref current_selection) =  self.left_widget()?.current_item {
+        if let Some(ref current_selection) =  self.parent_widget(depth)?.current_item {
             if current_selection.name == selection.name {
                 return Ok(());
             }
         }
 
-        self.left_widget_mut()?.select_file(&selection);
+        self.parent_widget_mut(depth)?.select_file(selection);
 
-        let selected_file = self.left_widget()?.selected_file();
-        self.cwd.parent_as_file()
-                .map(|dir| {
-                    self.fs_cache
-                        .set_selection(dir.clone(), selected_file.clone())
-                }).log();
+        let selected_file = self.parent_widget(depth)?.selected_file();
+        selection.parent_as_file()
+                 .map(|dir| {
+                     self.fs_cache
+                         .set_selection(dir.clone(), selected_file.clone())
+                 }).log();
 
 
         Ok(())
@@ -799,10 +1418,11 @@ impl FileBrowser {
         Ok(())
     }
 
-    pub fn left_dir(&self) -> WResult<&File> {
-        let widget = self.left_widget()?;
-        let dir = &widget.content.directory;
-        Ok(dir)
+    pub fn parent_dirs(&self) -> Vec<&File> {
+        (1..=self.parent_columns)
+            .filter_map(|depth| self.parent_widget(depth).ok())
+            .map(|widget| &widget.content.directory)
+            .collect()
     }
 
     pub fn selected_file(&self) -> WResult<File> {
@@ -849,44 +1469,89 @@ impl FileBrowser {
         widget
     }
 
-    pub fn left_async_widget_mut(&mut self) -> WResult<&mut AsyncWidget<ListView<Files>>> {
-        let widget = match self.columns.widgets.get_mut(0).ok_or(WError::NoneError)? {
+    fn main_index(&self) -> usize {
+        self.parent_columns
+    }
+
+    fn preview_index(&self) -> usize {
+        self.parent_columns + 1
+    }
+
+    fn other_index(&self) -> usize {
+        self.parent_columns + 2
+    }
+
+    // Depth 1 is the parent directory, 2 the one above that and so on
+    fn parent_index(&self, depth: usize) -> WResult<usize> {
+        if depth == 0 || depth > self.parent_columns {
+            return WError::no_widget();
+        }
+        Ok(self.parent_columns - depth)
+    }
+
+    pub fn parent_async_widget_mut(&mut self, depth: usize)
+                                   -> WResult<&mut AsyncWidget<ListView<Files>>> {
+        let index = self.parent_index(depth)?;
+        let widget = match self.columns.widgets.get_mut(index).ok_or(WError::NoneError)? {
             FileBrowserWidgets::FileList(filelist) => filelist,
             _ => { return WError::wrong_widget("previewer", "filelist"); }
         };
         Ok(widget)
     }
 
-    pub fn left_widget(&self) -> WResult<&ListView<Files>> {
-        let widget = match self.columns.widgets.get(0).ok_or(WError::NoneError)? {
+    pub fn parent_widget(&self, depth: usize) -> WResult<&ListView<Files>> {
+        let index = self.parent_index(depth)?;
+        let widget = match self.columns.widgets.get(index).ok_or(WError::NoneError)? {
             FileBrowserWidgets::FileList(filelist) => filelist.widget(),
             _ => { return WError::wrong_widget("previewer", "filelist"); }
         };
         widget
     }
 
-    pub fn left_widget_mut(&mut self) -> WResult<&mut ListView<Files>> {
-        let widget = match self.columns.widgets.get_mut(0).ok_or(WError::NoneError)? {
+    pub fn parent_widget_mut(&mut self, depth: usize) -> WResult<&mut ListView<Files>> {
+        let index = self.parent_index(depth)?;
+        let widget = match self.columns.widgets.get_mut(index).ok_or(WError::NoneError)? {
             FileBrowserWidgets::FileList(filelist) => filelist.widget_mut(),
             _ => { return WError::wrong_widget("previewer", "filelist"); }
         };
         widget
     }
 
+    pub fn left_async_widget_mut(&mut self) -> WResult<&mut AsyncWidget<ListView<Files>>> {
+        self.parent_async_widget_mut(1)
+    }
+
+    pub fn left_widget(&self) -> WResult<&ListView<Files>> {
+        self.parent_widget(1)
+    }
+
+    pub fn left_widget_mut(&mut self) -> WResult<&mut ListView<Files>> {
+        self.parent_widget_mut(1)
+    }
+
     pub fn preview_widget(&self) -> WResult<&Previewer> {
-        match self.columns.widgets.get(2).ok_or(WError::NoneError)? {
+        match self.columns.widgets.get(self.preview_index()).ok_or(WError::NoneError)? {
             FileBrowserWidgets::Previewer(previewer) => Ok(previewer),
             _ => { return WError::wrong_widget("filelist", "previewer"); }
         }
     }
 
     pub fn preview_widget_mut(&mut self) -> WResult<&mut Previewer> {
-        match self.columns.widgets.get_mut(2).ok_or(WError::NoneError)? {
+        let index = self.preview_index();
+        match self.columns.widgets.get_mut(index).ok_or(WError::NoneError)? {
             FileBrowserWidgets::Previewer(previewer) => Ok(previewer),
             _ => { return WError::wrong_widget("filelist", "previewer"); }
         }
     }
 
+    pub fn other_async_widget_mut(&mut self) -> WResult<&mut AsyncWidget<ListView<Files>>> {
+        let index = self.other_index();
+        match self.columns.widgets.get_mut(index).ok_or(WError::NoneError)? {
+            FileBrowserWidgets::FileList(filelist) => Ok(filelist),
+            _ => WError::wrong_widget("previewer", "filelist")
+        }
+    }
+
     fn cancel_preview_animation(&mut self) {
         self.preview_widget_mut()
             .map(|preview| preview.cancel_animation())
@@ -894,16 +1559,16 @@ impl FileBrowser {
     }
 
     fn activate_main_widget(&mut self) {
-        const MAIN_INDEX: usize = 1;
+        let main_index = self.main_index();
         self.columns
-            .set_active(MAIN_INDEX)
+            .set_active(main_index)
             .log();
     }
 
     fn activate_preview_widget(&mut self) {
-        const PREVIEW_INDEX: usize = 2;
+        let preview_index = self.preview_index();
         self.columns
-            .set_active(PREVIEW_INDEX)
+            .set_active(preview_index)
             .log();
     }
 
@@ -928,6 +1593,261 @@ impl FileBrowser {
             .log();
     }
 
+    fn preview_visible(&self) -> bool {
+        !self.columns.zoom_active &&
+            self.breakpoint() != Breakpoint::Single &&
+            (self.layout == Layout::Miller || self.pane_preview)
+    }
+
+    fn breakpoint(&self) -> Breakpoint {
+        let (narrow, single) = match self.core.config().breakpoints {
+            Some(breakpoints) => breakpoints,
+            None => return Breakpoint::Wide
+        };
+
+        let xsize = self.core.coordinates.xsize_u();
+        let ysize = self.core.coordinates.ysize_u();
+        // Characters are roughly twice as high as wide
+        let tall = ysize * 2 > xsize;
+
+        if xsize < single {
+            Breakpoint::Single
+        } else if xsize < narrow && tall {
+            Breakpoint::Tall
+        } else if xsize < narrow {
+            Breakpoint::Narrow
+        } else {
+            Breakpoint::Wide
+        }
+    }
+
+    fn update_layout(&mut self) {
+        let main_index = self.main_index();
+        let breakpoint = self.breakpoint();
+
+        self.columns.vertical = breakpoint == Breakpoint::Tall;
+
+        match (self.layout, breakpoint) {
+            (_, Breakpoint::Single) => {
+                self.columns.set_ratios(vec![100]);
+                self.columns.set_order(Some(vec![main_index]));
+            }
+            (Layout::Miller, Breakpoint::Wide) => {
+                self.columns.set_ratios(self.core.config().ratios);
+                self.columns.set_order(None);
+            }
+            // Drop the parent columns and keep main and preview
+            (Layout::Miller, _) => {
+                let ratios = self.core.config().ratios.split_off(main_index);
+                self.columns.set_ratios(ratios);
+                self.columns.set_order(Some(vec![main_index, self.preview_index()]));
+            }
+            (Layout::DualPane, _) => {
+                let other = match self.pane_preview {
+                    true => self.preview_index(),
+                    false => self.other_index()
+                };
+                let order = match self.active_pane {
+                    0 => vec![main_index, other],
+                    _ => vec![other, main_index]
+                };
+
+                self.columns.set_ratios(vec![50, 50]);
+                self.columns.set_order(Some(order));
+            }
+        }
+
+        self.columns.resize_children().log();
+    }
+
+    fn open_other_pane(&mut self) {
+        let dir = self.other_cwd.clone().unwrap_or_else(|| self.cwd.clone());
+        let cache = self.fs_cache.clone();
+        let core = self.core.clone();
+        let file_source = FileSource::Path(dir.clone());
+
+        let other_widget = AsyncWidget::new(&self.core, move |stale| {
+            ListView::builder(core, file_source)
+                .with_cache(cache)
+                .with_stale(stale.clone())
+                .build()
+        });
+
+        self.columns.push_widget(FileBrowserWidgets::FileList(other_widget));
+        self.other_cwd = Some(dir);
+        self.layout = Layout::DualPane;
+        self.update_layout();
+    }
+
+    fn close_other_pane(&mut self) {
+        // The other pane's directory is still in other_cwd for next time
+        self.columns.pop_widget();
+        self.active_pane = 0;
+        self.layout = Layout::Miller;
+        self.update_layout();
+    }
+
+    // Opens the other pane in other_cwd, regardless of what's open now
+    fn set_dual_pane(&mut self, dual_pane: bool, other_cwd: Option<File>) {
+        if self.layout == Layout::DualPane {
+            self.close_other_pane();
+        }
+        self.other_cwd = other_cwd;
+        if dual_pane {
+            self.open_other_pane();
+        }
+    }
+
+    pub fn toggle_dual_pane(&mut self) -> WResult<()> {
+        self.cancel_preview_animation();
+
+        if self.columns.zoom_active {
+            self.columns.toggle_zoom().log();
+        }
+        self.activate_main_widget();
+
+        match self.layout {
+            Layout::Miller => self.open_other_pane(),
+            Layout::DualPane => self.close_other_pane()
+        }
+
+        self.core.clear().log();
+        self.refresh()
+    }
+
+    pub fn switch_pane(&mut self) -> WResult<()> {
+        if self.layout != Layout::DualPane {
+            return self.core.show_status("There's no other pane to switch to!");
+        }
+
+        self.cancel_preview_animation();
+        self.save_selected_file().log();
+
+        let other_cwd = self.other_cwd.take().ok_or(WError::NoneError)?;
+        let (main_index, other_index) = (self.main_index(), self.other_index());
+        self.columns.widgets.swap(main_index, other_index);
+        self.other_cwd = Some(std::mem::replace(&mut self.cwd, other_cwd));
+        self.active_pane = 1 - self.active_pane;
+
+        self.parent_columns_goto(1).log();
+
+        self.preview_widget_mut()?.set_stale().log();
+        self.update_layout();
+        self.refresh()
+    }
+
+    pub fn captures_key(&self, key: Key) -> bool {
+        if self.tag_prefix.is_some() {
+            return true;
+        }
+
+        // Tab switches panes instead of tabs in the dual pane layout
+        match self.search_in().get(key) {
+            Some(FileBrowserAction::SwitchPane) => self.layout == Layout::DualPane,
+            _ => false
+        }
+    }
+
+    fn start_tag_prefix(&mut self, action: FileBrowserAction) -> WResult<()> {
+        let labels = crate::files::tag_labels()
+            .unwrap_or_default()
+            .iter()
+            .map(|(label, count)| format!("{}({})", label, count))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.tag_prefix = Some(action);
+        self.core.show_status(&format!("Tag label: {}", labels))
+    }
+
+    fn tag_label_key(&mut self, action: FileBrowserAction, key: Key) -> WResult<()> {
+        use crate::keybind::TagAction;
+
+        let label = match self.core.config().keybinds.tag.get_any(key) {
+            Some(TagAction::Label(label)) => label,
+            _ => return self.core.clear_status()
+        };
+
+        match action {
+            FileBrowserAction::SelectTagged => self.main_widget_mut()?.select_tagged(label),
+            FileBrowserAction::FilterTagged => self.main_widget_mut()?.filter_tagged(label),
+            _ => self.main_widget_mut()?.toggle_tag_label(label)?
+        }
+
+        Ok(())
+    }
+
+    pub fn toggle_pane_preview(&mut self) -> WResult<()> {
+        if self.layout != Layout::DualPane {
+            return self.core.show_status("The preview only replaces the other pane!");
+        }
+
+        self.cancel_preview_animation();
+        self.pane_preview = !self.pane_preview;
+        self.preview_widget_mut()?.set_stale().log();
+        self.update_layout();
+        self.core.clear().log();
+        self.refresh()
+    }
+
+    fn transfer_to_other_pane(&mut self, cmd: &str, flags: &[&str]) -> WResult<()> {
+        let target = match self.layout {
+            Layout::DualPane => self.other_cwd.clone().ok_or(WError::NoneError)?,
+            Layout::Miller => {
+                return self.core.show_status("Copying and moving needs the dual pane layout!");
+            }
+        };
+
+        let files = self.action_files()?;
+
+        // Neither cp nor mv would ask before replacing these
+        let existing = files.iter()
+                            .filter_map(|file| file.path.file_name())
+                            .filter(|name| target.path.join(name).symlink_metadata().is_ok())
+                            .count();
+        if existing > 0 {
+            let query = format!("overwrite {} files in {}? (y/n)",
+                                existing,
+                                target.short_string());
+            match self.core.minibuffer(&query)?.as_str() {
+                "y" | "yes" => {}
+                _ => return self.core.show_status("Nothing copied or moved"),
+            }
+        }
+
+        let names = files.iter()
+                         .map(|file| file.name.clone())
+                         .collect::<Vec<_>>()
+                         .join(" ");
+
+        let args = flags.iter()
+                        .map(OsString::from)
+                        .chain(std::iter::once(OsString::from("--")))
+                        .chain(files.iter().map(|file| file.path.clone().into_os_string()))
+                        .chain(std::iter::once(target.path.clone().into_os_string()))
+                        .collect();
+
+        let cmd = crate::proclist::Cmd {
+            cmd: OsString::from(cmd),
+            short_cmd: Some(format!("{} {} {}", cmd, names, target.short_string())),
+            args: Some(args),
+            vars: None,
+            cwd: self.cwd.clone(),
+            cwd_file: None,
+            prev_cwd: None,
+            cwd_files: None,
+            tab_files: None,
+            tab_paths: None,
+            input: None
+        };
+
+        self.proc_view
+            .lock()
+            .run_proc_raw(cmd)?;
+
+        Ok(())
+    }
+
     pub fn quit_with_dir(&self) -> WResult<()> {
         let cwd = self.cwd()?.clone().path;
         let selected_file = self.selected_file()?;
@@ -951,6 +1871,20 @@ impl FileBrowser {
         WError::quit()
     }
 
+    fn move_main_widget(&mut self, movement: &Movement, count: Option<usize>) -> WResult<()> {
+        let pos = self.main_widget()?.get_selection();
+        match count {
+            Some(count) => self.main_widget_mut()?.movement_count(movement, count)?,
+            None => self.main_widget_mut()?.movement(movement)?,
+        }
+        if self.main_widget()?.get_selection() != pos {
+            self.preview_widget_mut()?.set_stale().log();
+            self.preview_widget_mut()?.cancel_animation().log();
+        }
+        self.save_selected_file()?;
+        Ok(())
+    }
+
     pub fn turbo_cd(&mut self) -> WResult<()> {
         use crate::minibuffer::MiniBufferEvent::*;
 
@@ -1270,12 +2204,61 @@ impl FileBrowser {
     fn exec_cmd(&mut self,
                 tab_dirs: Vec<File>,
                 tab_files: Vec<Vec<File>>) -> WResult<()> {
+        let cmd = self.core.minibuffer("exec")?;
+        let (cmd, fg) = crate::proclist::foreground(&cmd);
+        let cmd = self.tab_cmd(cmd.to_string(), tab_dirs, tab_files)?;
+
+        self.proc_view
+            .lock()
+            .run_proc_subshell(cmd, fg)?;
+
+        Ok(())
+    }
+
+    fn run_user_command(&mut self,
+                        n: usize,
+                        tab_dirs: Vec<File>,
+                        tab_files: Vec<Vec<File>>) -> WResult<()> {
+        let command = self.core.config().keybinds.commands.get(n).cloned()
+            .ok_or(WError::NoneError)?;
+
+        let input = match command.prompt {
+            true => Some(self.core.minibuffer("input")?),
+            false => None
+        };
+
+        let mut cmd = self.tab_cmd(command.cmd, tab_dirs, tab_files)?;
+        cmd.input = input;
+
+        match command.wait {
+            true => self.proc_view.lock().run_proc_subshell_wait(cmd, command.fg)?,
+            false => self.proc_view.lock().run_proc_subshell(cmd, command.fg)?
+        }
+
+        // Only foreground commands can refresh, they are done by now
+        if command.refresh {
+            self.reload_main_widget()?;
+        }
+
+        Ok(())
+    }
+
+    // Everything the patterns of an ExecCmd stand for
+    fn tab_cmd(&self,
+               cmd: String,
+               tab_dirs: Vec<File>,
+               tab_files: Vec<Vec<File>>) -> WResult<crate::proclist::Cmd> {
         let cwd = self.cwd()?.clone();
         let selected_file = self.selected_file().ok();
         let selected_files = self.selected_files().ok();
 
-        let cmd = self.core.minibuffer("exec")?.to_string();
+        // A basket in use stands in for the selection, "$b" is always it
+        let selected_files = match crate::basket::is_empty() {
+            true => selected_files,
+            false => crate::basket::files().ok()
+        };
 
+        let cwd_file = selected_file.clone();
         let cwd_files = selected_files.map(|selected_files| {
             if selected_files.len() == 0 {
                 if selected_file.is_some() {
@@ -1293,15 +2276,41 @@ impl FileBrowser {
             short_cmd: None,
             args: None,
             vars: None,
-            cwd: cwd,
-            cwd_files: cwd_files,
+            cwd,
+            cwd_file,
+            prev_cwd: self.prev_cwd.clone(),
+            cwd_files,
             tab_files: Some(tab_files),
-            tab_paths: Some(tab_dirs)
+            tab_paths: Some(tab_dirs),
+            input: None
+        };
+
+        Ok(cmd)
+    }
+
+    // For files from anywhere, "$s" gets these instead of the selection
+    fn exec_cmd_on(&mut self, files: Vec<File>) -> WResult<()> {
+        let cwd = self.cwd()?.clone();
+        let cmd = self.core.minibuffer("exec")?;
+        let (cmd, fg) = crate::proclist::foreground(&cmd);
+
+        let cmd = crate::proclist::Cmd {
+            cmd: OsString::from(cmd),
+            short_cmd: None,
+            args: None,
+            vars: None,
+            cwd,
+            cwd_file: self.selected_file().ok(),
+            prev_cwd: self.prev_cwd.clone(),
+            cwd_files: Some(files),
+            tab_files: None,
+            tab_paths: None,
+            input: None
         };
 
         self.proc_view
             .lock()
-            .run_proc_subshell(cmd)?;
+            .run_proc_subshell(cmd, fg)?;
 
         Ok(())
     }
@@ -1371,11 +2380,109 @@ impl FileBrowser {
         Ok(())
     }
 
-    pub fn quick_action(&self) -> WResult<()> {
+    pub fn add_to_basket(&mut self) -> WResult<()> {
+        let selected_files = self.selected_files()?;
+        let selected_file = self.selected_file()?;
+
+        // Without a selection the current file is toggled
+        if selected_files.is_empty() && crate::basket::contains(&selected_file) {
+            crate::basket::remove(&selected_file)?;
+            let status = format!("Removed {} from basket, {} files in basket",
+                                 selected_file.name,
+                                 crate::basket::len());
+            return self.core.show_status(&status);
+        }
+
+        let files = if !selected_files.is_empty() { selected_files }
+        else { vec![selected_file] };
+
+        let added = crate::basket::add(&files)?;
+        let status = format!("Added {} files to basket, {} files in basket",
+                             added,
+                             crate::basket::len());
+        self.core.show_status(&status)
+    }
+
+    pub fn show_basket(&mut self) -> WResult<()> {
+        if crate::basket::is_empty() {
+            return self.core.show_status("Basket is empty!");
+        }
+
+        let mut view = BasketView::new(&self.core, self.fs_cache.clone(), Basket)?;
+        let pick = self.run_list_popup(&mut view)?;
+        self.on_file_pick(pick)
+    }
+
+    pub fn show_tagged(&mut self) -> WResult<()> {
+        if crate::files::tagged_paths().map(|paths| paths.is_empty()).unwrap_or(true) {
+            return self.core.show_status("Nothing tagged yet!");
+        }
+
+        let mut view = TaggedView::new(&self.core, self.fs_cache.clone(), Tagged)?;
+        let pick = self.run_list_popup(&mut view)?;
+        self.on_file_pick(pick)
+    }
+
+    // Shows the popup until something is picked or it's closed
+    fn run_list_popup<L>(&mut self, view: &mut PathListView<L>) -> WResult<Option<L::Pick>>
+    where
+        L: PathList,
+        L::Action: BindingSection + Debug,
+        Bindings<L::Action>: Default,
+    {
+        self.preview_widget().map(|preview| preview.cancel_animation()).log();
+
+        loop {
+            match view.pick() {
+                // Ignore refresh
+                Err(WError::RefreshParent) => continue,
+                Err(WError::TerminalResizedError) |
+                Err(WError::WidgetResizedError) => {
+                    self.resize().log();
+                    view.set_coordinates(&self.core.coordinates).log();
+                }
+                result => return result
+            }
+        }
+    }
+
+    fn on_file_pick(&mut self, pick: Option<FilePick>) -> WResult<()> {
+        match pick {
+            Some(FilePick::Goto(file)) => self.goto_file(&file),
+            Some(FilePick::QuickActions(files)) => self.quick_action_on(files),
+            Some(FilePick::ExecCmd(files)) => self.exec_cmd_on(files),
+            None => Ok(())
+        }
+    }
+
+    pub fn goto_file(&mut self, file: &File) -> WResult<()> {
+        let dir = file.parent_as_file()?;
+        self.fs_cache.set_selection(dir.clone(), file.clone())?;
+        self.main_widget_goto(&dir)
+    }
+
+    // The selection, or the file under the cursor
+    pub fn action_files(&self) -> WResult<Vec<File>> {
         let files = self.selected_files()?;
-        let files = if files.len() > 0 { files }
-        else { vec![self.selected_file()?.clone()] };
 
+        match files.len() {
+            0 => Ok(vec![self.selected_file()?]),
+            _ => Ok(files)
+        }
+    }
+
+    // Like "$s", the basket is used instead of the selection when it's
+    // not empty
+    pub fn quick_action(&self) -> WResult<()> {
+        let files = match crate::basket::is_empty() {
+            true => self.action_files()?,
+            false => crate::basket::files()?
+        };
+
+        self.quick_action_on(files)
+    }
+
+    pub fn quick_action_on(&self, files: Vec<File>) -> WResult<()> {
         let sender = self.core.get_sender();
         let core = self.preview_widget()?.get_core()?.clone();
         let proc_view = self.proc_view.clone();
@@ -1410,6 +2517,11 @@ impl FileBrowser {
                                  selection,
                                  file_count,
                                  digits = digits);
+        // Show the basket when it's in use
+        let file_count = match crate::basket::len() {
+            0 => file_count,
+            basket_count => format!("basket:{} {}", basket_count, file_count)
+        };
         let count_xpos = xsize - file_count.len() as u16;
         let count_ypos = ypos + self.get_coordinates()?.ysize();
 
@@ -1463,6 +2575,8 @@ impl Widget for FileBrowser {
     fn set_coordinates(&mut self, coordinates: &Coordinates) -> WResult<()> {
         self.core.coordinates = coordinates.clone();
         self.columns.set_coordinates(&coordinates).log();
+        // Switches to a narrower layout when needed
+        self.update_layout();
 
         self.proc_view
             .lock()
@@ -1515,13 +2629,11 @@ impl Widget for FileBrowser {
                              .lock();
         let status = status.as_mut()
                            .take();
-        let active = self.columns
-                         .active
-                         .unwrap_or(1);
+        let preview_active = self.columns.active == Some(self.preview_index());
 
-        match (status, active) {
+        match (status, preview_active) {
             (Some(status), _) => Ok(term::sized_string_u(&status, xsize)),
-            (_,            2) => self.preview_widget()?.render_footer(),
+            (_,         true) => self.preview_widget()?.render_footer(),
             _                 => self.get_footer(),
         }
     }
@@ -1529,9 +2641,9 @@ impl Widget for FileBrowser {
     fn refresh(&mut self) -> WResult<()> {
         self.set_title().log();
         self.columns.refresh().log();
-        self.set_left_selection().log();
+        self.set_parent_selections().log();
         self.set_cwd().log();
-        if !self.columns.zoom_active { self.update_preview().log(); }
+        if self.preview_visible() { self.update_preview().log(); }
         self.columns.refresh().log();
         Ok(())
     }
@@ -1541,20 +2653,25 @@ impl Widget for FileBrowser {
     }
 
     fn on_key(&mut self, key: Key) -> WResult<()> {
+        if let Some(action) = self.tag_prefix.take() {
+            return self.tag_label_key(action, key);
+        }
+
         // Special handling for preview zoom
         let binds = self.search_in();
         let action = binds.get(key);
+        let preview_active = self.columns.active == Some(self.preview_index());
 
-        match (action, self.columns.active) {
-            (Some(FileBrowserAction::ZoomPreview), Some(2)) => {
+        match (action, preview_active) {
+            (Some(FileBrowserAction::ZoomPreview), true) => {
                 self.toggle_colums();
                 return Ok(());
             }
-            (Some(FileBrowserAction::ZoomPreview), Some(1)) => {
+            (Some(FileBrowserAction::ZoomPreview), false) => {
                 self.zoom_preview();
                 return Ok(());
             }
-            (_, Some(2)) => {
+            (_, true) => {
                 self.columns.active_widget_mut().ok_or(WError::NoneError)?.on_key(key)?;
                 return Ok(());
             }
@@ -1567,7 +2684,12 @@ impl Widget for FileBrowser {
                     Ok(_) => {
                         self.save_tab_settings()?;
                     }
+                    // The keys of the sequence get replayed on their own
+                    e @ Err(WError::WidgetUndefinedKeyError{..})
+                        if self.core.pending_keys.lock().went_nowhere() => e?,
                     Err(WError::WidgetUndefinedKeyError{..}) => {
+                        // Nobody wanted the key, so a pending count is stale now
+                        self.core.take_count();
                         self.preview_widget_mut()?.on_key(key)?
                     }
                     e @ _ => e?
@@ -1576,12 +2698,12 @@ impl Widget for FileBrowser {
             e @ _ => e?
         };
 
-        if !self.columns.zoom_active { self.update_preview().log(); }
+        if self.preview_visible() { self.update_preview().log(); }
         Ok(())
     }
 }
 
-use crate::keybind::{Acting, Bindings, FileBrowserAction, Movement};
+use crate::keybind::{Acting, AnyKey, BindingSection, Bindings, FileBrowserAction, Movement};
 
 impl Acting for FileBrowser {
     type Action=FileBrowserAction;
@@ -1590,25 +2712,53 @@ impl Acting for FileBrowser {
         self.core.config().keybinds.filebrowser
     }
 
+    fn describe(&self, action: &Self::Action) -> String {
+        self.core.config().keybinds.filebrowser_name(action)
+    }
+
     fn movement(&mut self, movement: &Movement) -> WResult<()> {
         use Movement::*;
 
         match movement {
             Left => self.go_back(),
             Right => self.enter_dir(),
-            _ => {
-                let pos = self.main_widget()?.get_selection();
-                self.main_widget_mut()?.movement(movement)?;
-                if self.main_widget()?.get_selection() != pos {
-                    self.preview_widget_mut()?.set_stale().log();
-                    self.preview_widget_mut()?.cancel_animation().log();
+            _ => self.move_main_widget(movement, None),
+        }
+    }
+
+    fn movement_count(&mut self, movement: &Movement, count: usize) -> WResult<()> {
+        use Movement::*;
+
+        match movement {
+            Left | Right => {
+                for _ in 0..count {
+                    self.movement(movement)?;
+                }
+                Ok(())
+            }
+            _ => self.move_main_widget(movement, Some(count)),
+        }
+    }
+
+    fn do_action_count(&mut self, action: &Self::Action, count: usize) -> WResult<()> {
+        use FileBrowserAction::*;
+
+        match action {
+            LeftColumnDown | LeftColumnUp => {
+                for _ in 0..count {
+                    self.do_action(action)?;
                 }
-                self.save_selected_file()?;
                 Ok(())
             }
+            _ => self.do_action(action),
         }
     }
 
+    fn takes_count(&self, key: Key) -> bool {
+        // Don't steal digits bound in the file list
+        self.core.config().keybinds.filelist.get(key).is_none()
+    }
+
     fn do_action(&mut self, action: &Self::Action) -> WResult<()> {
         use FileBrowserAction::*;
         match action {
@@ -1631,7 +2781,23 @@ impl Acting for FileBrowser {
             ToggleColumns => self.toggle_colums(),
             ZoomPreview => self.zoom_preview(),
             // Tab implementation needs to call exec_cmd because ALL files are needed
-            ExecCmd => Err(WError::FileBrowserNeedTabFiles)?
+            ExecCmd => Err(WError::FileBrowserNeedTabFiles)?,
+            AddToBasket => self.add_to_basket()?,
+            ShowBasket => self.show_basket()?,
+            ToggleDualPane => self.toggle_dual_pane()?,
+            SwitchPane => self.switch_pane()?,
+            TogglePanePreview => self.toggle_pane_preview()?,
+            CopyToOtherPane => self.transfer_to_other_pane("cp", &["-R"])?,
+            MoveToOtherPane => self.transfer_to_other_pane("mv", &[])?,
+            TagWith | SelectTagged | FilterTagged => self.start_tag_prefix(*action)?,
+            ShowTagged => self.show_tagged()?,
+            JumpFrecent => self.jump_frecent()?,
+            Back => self.go_back_in_history()?,
+            Forward => self.go_forward_in_history()?,
+            ShowJumpList => self.show_jump_list()?,
+            ShowCommandPalette => self.show_command_palette()?,
+            ShowHelp => self.show_help()?,
+            RunCommand(n) => Err(WError::FileBrowserRunCommand(*n))?
         }
         Ok(())
     }
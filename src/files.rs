@@ -1,6 +1,6 @@
 use std::cmp::Ord;
-use std::collections::{HashMap, HashSet};
-use std::ffi::OsStr;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
 use std::fs::Metadata;
 use std::hash::{Hash, Hasher};
 use std::ops::Index;
@@ -33,7 +33,8 @@ use crate::widget::Events;
 
 lazy_static! {
     static ref COLORS: LsColors = LsColors::from_env().unwrap_or_default();
-    static ref TAGS: RwLock<(bool, Vec<PathBuf>)> = RwLock::new((false, vec![]));
+    static ref TAGS: RwLock<(bool, BTreeMap<PathBuf, Vec<char>>)> =
+        RwLock::new((false, BTreeMap::new()));
     static ref ICONS: Icons = Icons::new();
     static ref IOTICK_CLIENTS: AtomicUsize = AtomicUsize::default();
     static ref IOTICK: AtomicUsize = AtomicUsize::default();
@@ -120,11 +121,17 @@ pub fn load_tags() -> WResult<()> {
         }
 
         let tags = std::fs::read_to_string(tag_path)?;
-        let mut tags = tags.lines().map(PathBuf::from).collect::<Vec<PathBuf>>();
-        tags.sort();
         let mut tag_lock = TAGS.write()?;
+
+        for line in tags.lines() {
+            let (label, path) = parse_tag_line(line);
+            let labels = tag_lock.1.entry(path).or_insert_with(Vec::new);
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+
         tag_lock.0 = true;
-        tag_lock.1.append(&mut tags);
         Ok(())
     });
     Ok(())
@@ -141,14 +148,108 @@ pub fn import_tags() -> WResult<()> {
     Ok(())
 }
 
-pub fn check_tag(path: &PathBuf) -> WResult<bool> {
+// Plain paths are tagged with "*", other labels are written like
+// "a:/path", same as ranger does it
+pub const DEFAULT_TAG: char = '*';
+
+fn parse_tag_line(line: &str) -> (char, PathBuf) {
+    let mut chars = line.chars();
+
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(label), Some(':'), Some('/')) => (label, PathBuf::from(&line[label.len_utf8() + 1..])),
+        _ => (DEFAULT_TAG, PathBuf::from(line)),
+    }
+}
+
+pub fn check_tags(path: &PathBuf) -> WResult<Vec<char>> {
     tags_loaded()?;
-    let tagged = TAGS
+    let tags = TAGS.read()?.1.get(path).cloned().unwrap_or_default();
+    Ok(tags)
+}
+
+pub fn tagged_with(label: char) -> WResult<Vec<PathBuf>> {
+    tags_loaded()?;
+    let paths = TAGS
         .read()?
         .1
-        .binary_search(path)
-        .map_or_else(|_| false, |_| true);
-    Ok(tagged)
+        .iter()
+        .filter(|(_, labels)| labels.contains(&label))
+        .map(|(path, _)| path.clone())
+        .collect();
+    Ok(paths)
+}
+
+// Tagged paths, sorted by their parent directory and then by name
+pub fn tagged_paths() -> WResult<Vec<PathBuf>> {
+    tags_loaded()?;
+    let mut paths = TAGS.read()?.1.keys().cloned().collect::<Vec<_>>();
+    paths.sort_by(|a, b| (a.parent(), a.file_name()).cmp(&(b.parent(), b.file_name())));
+    Ok(paths)
+}
+
+// Unlike File::save_tags this is done when it returns
+pub fn untag(paths: &[PathBuf]) -> WResult<()> {
+    let mut tags = TAGS.write()?;
+
+    for path in paths {
+        tags.1.remove(path);
+    }
+
+    write_tags(&tags.1)
+}
+
+fn tag_lines(tags: &BTreeMap<PathBuf, Vec<char>>) -> OsString {
+    tags.iter().fold(OsString::new(), |mut s, (f, labels)| {
+        for label in labels {
+            if *label != DEFAULT_TAG {
+                s.push(format!("{}:", label));
+            }
+            s.push(f);
+            s.push("\n");
+        }
+        s
+    })
+}
+
+fn write_tags(tags: &BTreeMap<PathBuf, Vec<char>>) -> WResult<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let tagfile_path = crate::paths::tagfile_path()?;
+    crate::paths::write_atomic(&tagfile_path, tag_lines(tags).as_bytes())
+}
+
+#[test]
+fn test_tag_lines() {
+    assert_eq!(parse_tag_line("/plain/path"), (DEFAULT_TAG, PathBuf::from("/plain/path")));
+    assert_eq!(parse_tag_line("a:/labeled"), ('a', PathBuf::from("/labeled")));
+    assert_eq!(parse_tag_line("ä:/unicode"), ('ä', PathBuf::from("/unicode")));
+    // Only a label when a path follows
+    assert_eq!(parse_tag_line("a:b"), (DEFAULT_TAG, PathBuf::from("a:b")));
+
+    let mut tags = BTreeMap::new();
+    tags.insert(PathBuf::from("/x"), vec![DEFAULT_TAG, 'b']);
+    tags.insert(PathBuf::from("/y:z"), vec!['c']);
+
+    let lines = tag_lines(&tags).into_string().unwrap();
+    assert_eq!(lines, "/x\nb:/x\nc:/y:z\n");
+
+    let parsed = lines.lines().map(parse_tag_line).collect::<Vec<_>>();
+    assert_eq!(parsed, vec![(DEFAULT_TAG, PathBuf::from("/x")),
+                            ('b', PathBuf::from("/x")),
+                            ('c', PathBuf::from("/y:z"))]);
+}
+
+// All labels in use, with the number of files tagged with each
+pub fn tag_labels() -> WResult<Vec<(char, usize)>> {
+    tags_loaded()?;
+    let labels = TAGS.read()?.1.values().flatten().fold(
+        BTreeMap::new(),
+        |mut labels, label| {
+            *labels.entry(*label).or_insert(0) += 1;
+            labels
+        },
+    );
+    Ok(labels.into_iter().collect())
 }
 
 pub fn tags_loaded() -> WResult<()> {
@@ -306,6 +407,8 @@ pub struct Files {
     pub show_hidden: bool,
     pub filter: Option<String>,
     pub filter_selected: bool,
+    // Only files tagged with this label are shown
+    pub filter_tag: Option<char>,
     pub dirty: DirtyBit,
     #[derivative(Debug = "ignore")]
     #[derivative(PartialEq = "ignore")]
@@ -359,6 +462,7 @@ impl Default for Files {
             show_hidden: false,
             filter: None,
             filter_selected: false,
+            filter_tag: None,
             dirty: DirtyBit::new(),
             jobs: vec![],
             cache: None,
@@ -576,7 +680,7 @@ pub fn from_getdents(
                         target: target,
                         meta: None,
                         selected: false,
-                        tag: None,
+                        tags: None,
                     };
 
                     // Push into local Vec
@@ -797,13 +901,33 @@ impl Files {
     pub fn filter_fn(&self) -> impl Fn(&File) -> bool + 'static {
         let filter = self.filter.clone();
         let filter_selected = self.filter_selected;
+        let filter_tag = self.filter_tag;
         let show_hidden = self.show_hidden;
 
+        // Looking each file up in TAGS would lock it for every single one
+        let tagged = filter_tag
+            .and_then(|label| tagged_with(label).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<HashSet<PathBuf>>();
+        let has_tag = move |f: &File, label: char| match &f.tags {
+            Some(tags) => tags.contains(&label),
+            None => tagged.contains(&f.path),
+        };
+
+        // Globs have to match the whole name, anything else only a part
+        let matches_filter = move |name: &str| match &filter {
+            Some(filter) if crate::shell::is_glob(filter) => crate::shell::glob_match(filter, name),
+            Some(filter) => name.contains(filter.as_str()),
+            None => true
+        };
+
         move |f| {
             f.kind == Kind::Placeholder
-                || !(filter.is_some() && !f.name.contains(filter.as_ref().unwrap()))
+                || matches_filter(&f.name)
                     && (!filter_selected || f.selected)
-                    && !(!show_hidden && f.name.starts_with("."))
+                    && filter_tag.map(|label| has_tag(f, label)).unwrap_or(true)
+                    && (show_hidden || !f.name.starts_with("."))
         }
     }
 
@@ -1015,7 +1139,15 @@ impl Files {
 
     pub fn set_filter(&mut self, filter: Option<String>) {
         self.filter = filter;
+        self.refilter();
+    }
+
+    pub fn set_filter_tag(&mut self, filter_tag: Option<char>) {
+        self.filter_tag = filter_tag;
+        self.refilter();
+    }
 
+    fn refilter(&mut self) {
         // Do this first, so we know len() == 0 needs a placeholder
         self.remove_placeholder();
 
@@ -1113,7 +1245,7 @@ pub struct File {
     pub target: Option<PathBuf>,
     pub meta: Option<Arc<RwLock<Option<Metadata>>>>,
     pub selected: bool,
-    pub tag: Option<bool>,
+    pub tags: Option<Vec<char>>,
 }
 
 impl File {
@@ -1133,7 +1265,7 @@ impl File {
             target: None,
             meta: None,
             selected: false,
-            tag: None,
+            tags: None,
         }
     }
 
@@ -1193,7 +1325,7 @@ impl File {
             target: target,
             meta: None,
             selected: false,
-            tag: None,
+            tags: None,
         }
     }
 
@@ -1440,74 +1572,59 @@ impl File {
         self.selected
     }
 
-    pub fn is_tagged(&self) -> WResult<bool> {
-        if let Some(tag) = self.tag {
-            return Ok(tag);
+    pub fn get_tags(&self) -> WResult<Vec<char>> {
+        if let Some(tags) = &self.tags {
+            return Ok(tags.clone());
         }
-        let tag = check_tag(&self.path)?;
-        Ok(tag)
+        check_tags(&self.path)
     }
 
-    pub fn set_tag_status(&mut self, tags: &[PathBuf]) {
-        match tags.contains(&self.path) {
-            true => self.tag = Some(true),
-            false => self.tag = Some(false),
-        }
+    pub fn is_tagged(&self) -> WResult<bool> {
+        Ok(!self.get_tags()?.is_empty())
+    }
+
+    pub fn has_tag(&self, label: char) -> bool {
+        self.get_tags()
+            .map(|tags| tags.contains(&label))
+            .unwrap_or(false)
     }
 
     pub fn toggle_tag(&mut self) -> WResult<()> {
-        let new_state = match self.tag {
-            Some(tag) => !tag,
-            None => {
-                let tag = check_tag(&self.path);
-                !tag?
+        self.toggle_tag_label(DEFAULT_TAG)
+    }
+
+    pub fn toggle_tag_label(&mut self, label: char) -> WResult<()> {
+        let mut tags = self.get_tags()?;
+
+        match tags.iter().position(|tag| *tag == label) {
+            Some(pos) => {
+                tags.remove(pos);
             }
-        };
-        self.tag = Some(new_state);
+            None => tags.push(label),
+        }
+        self.tags = Some(tags);
 
         self.save_tags()?;
         Ok(())
     }
 
     pub fn save_tags(&self) -> WResult<()> {
-        if self.tag.is_none() {
-            return Ok(());
-        }
+        let labels = match &self.tags {
+            Some(tags) => tags.clone(),
+            None => return Ok(()),
+        };
 
         let path = self.path.clone();
-        let state = self.tag.unwrap();
 
         std::thread::spawn(move || -> WResult<()> {
-            use std::os::unix::ffi::OsStrExt;
-
-            let tagfile_path = crate::paths::tagfile_path()?;
             let mut tags = TAGS.write()?;
 
-            match state {
-                true => {
-                    match tags.1.binary_search(&path) {
-                        Ok(_) => {}
-                        Err(inspos) => tags.1.insert(inspos, path),
-                    };
-                }
-                false => {
-                    match tags.1.binary_search(&path) {
-                        Ok(delpos) => {
-                            tags.1.remove(delpos);
-                        }
-                        Err(_) => {}
-                    };
-                }
-            }
-
-            let tagstr = tags.1.iter().fold(std::ffi::OsString::new(), |mut s, f| {
-                s.push(f);
-                s.push("\n");
-                s
-            });
+            match labels.is_empty() {
+                true => tags.1.remove(&path),
+                false => tags.1.insert(path, labels),
+            };
 
-            std::fs::write(tagfile_path, tagstr.as_bytes())?;
-            Ok(())
+            write_tags(&tags.1)
         });
         Ok(())
     }
@@ -1617,7 +1734,25 @@ impl File {
     }
 
     pub fn icon(&self) -> &'static str {
-        ICONS.get(&self.path)
+        self.icon_with_color(false).0
+    }
+
+    // "open" is set for directories whose contents are currently shown
+    pub fn icon_with_color(&self, open: bool) -> (&'static str, Option<&'static str>) {
+        use crate::icon::IconState;
+
+        let executable = self.meta()
+            .and_then(|meta| meta.as_ref().map(|meta| meta.mode() & 0o111 != 0))
+            .unwrap_or(false);
+
+        let state = IconState {
+            dir: self.is_dir(),
+            open,
+            symlink: self.target.is_some(),
+            executable,
+        };
+
+        ICONS.get_with_state(&self.path, state)
     }
 
     pub fn short_path(&self) -> PathBuf {
@@ -0,0 +1,328 @@
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::fail::{WError, WResult};
+
+// Once all ranks add up to more than this they get aged, like z does it
+const MAX_RANK: f64 = 9000.0;
+
+lazy_static! {
+    static ref DB: RwLock<Option<Vec<Entry>>> = RwLock::new(None);
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    path: PathBuf,
+    rank: f64,
+    time: u64,
+}
+
+impl Entry {
+    // Same format as z uses: path|rank|time
+    fn parse(line: &str) -> Option<Entry> {
+        let mut parts = line.rsplitn(3, '|');
+        let time = parts.next()?.trim().parse().ok()?;
+        let rank = parts.next()?.trim().parse().ok()?;
+        let path = PathBuf::from(parts.next()?);
+
+        Some(Entry { path, rank, time })
+    }
+
+    // Directories visited recently count for more
+    fn frecency(&self, now: u64) -> f64 {
+        match now.saturating_sub(self.time) {
+            0..=3600 => self.rank * 4.0,
+            3601..=86400 => self.rank * 2.0,
+            86401..=604800 => self.rank / 2.0,
+            _ => self.rank / 4.0,
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+fn with_db<T>(f: impl FnOnce(&mut Vec<Entry>) -> T) -> WResult<T> {
+    let mut db = DB.write()?;
+
+    // Nothing is cached when loading fails, so a broken database is never
+    // saved over
+    if db.is_none() {
+        *db = Some(load()?);
+    }
+
+    Ok(f(db.get_or_insert_with(Vec::new)))
+}
+
+// Read again right before writing, so visits from other instances aren't
+// lost. The lock keeps threads of this one from writing at the same time.
+fn update(f: impl FnOnce(&mut Vec<Entry>)) -> WResult<()> {
+    let mut db = DB.write()?;
+    let mut entries = load()?;
+
+    f(&mut entries);
+    save(&entries)?;
+
+    *db = Some(entries);
+    Ok(())
+}
+
+fn load() -> WResult<Vec<Entry>> {
+    let db_path = crate::paths::frecency_path()?;
+    let db = match std::fs::read_to_string(db_path) {
+        Ok(db) => db,
+        // No directory visited yet
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+
+    parse_db(&db)
+}
+
+fn parse_db(db: &str) -> WResult<Vec<Entry>> {
+    db.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            Entry::parse(line)
+                .ok_or_else(|| WError::Log(format!("Broken line in frecency database: {}",
+                                                   line)))
+        })
+        .collect()
+}
+
+fn save(entries: &[Entry]) -> WResult<()> {
+    let db_path = crate::paths::frecency_path()?;
+
+    let db = entries.iter().fold(String::new(), |mut db, entry| {
+        db += &format!("{}|{}|{}\n",
+                       entry.path.to_string_lossy(),
+                       entry.rank,
+                       entry.time);
+        db
+    });
+
+    crate::paths::write_atomic(&db_path, db.as_bytes())
+}
+
+fn add_rank(entries: &mut Vec<Entry>, path: PathBuf, rank: f64, time: u64) {
+    match entries.iter_mut().find(|entry| entry.path == path) {
+        Some(entry) => {
+            entry.rank += rank;
+            entry.time = entry.time.max(time);
+        }
+        None => entries.push(Entry { path, rank, time }),
+    }
+}
+
+fn age(entries: &mut Vec<Entry>) {
+    let total = entries.iter().map(|entry| entry.rank).sum::<f64>();
+
+    if total > MAX_RANK {
+        entries.iter_mut().for_each(|entry| entry.rank *= 0.99);
+        entries.retain(|entry| entry.rank >= 1.0);
+    }
+}
+
+// Called whenever a directory is entered
+pub fn visit(dir: &Path) {
+    let path = dir.to_path_buf();
+
+    // Don't make navigation wait for the disk
+    std::thread::spawn(move || -> WResult<()> {
+        update(|entries| {
+            add_rank(entries, path, 1.0, now());
+            age(entries);
+        })
+    });
+}
+
+// Every query character has to show up in order, but matches in the
+// directory name itself beat those anywhere else in the path
+fn match_tier(path: &Path, query: &str) -> Option<usize> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let path = path.to_string_lossy().to_lowercase();
+
+    if name.contains(query) {
+        Some(0)
+    } else if path.contains(query) {
+        Some(1)
+    } else {
+        let mut chars = path.chars();
+        match query.chars().all(|qc| chars.any(|c| c == qc)) {
+            true => Some(2),
+            false => None,
+        }
+    }
+}
+
+// Existing directories matching the query, best match first
+pub fn query(query: &str) -> WResult<Vec<PathBuf>> {
+    let now = now();
+    let matches = with_db(|entries| rank(entries, query, now))?;
+
+    Ok(matches.into_iter()
+       .filter(|path| path.is_dir())
+       .collect())
+}
+
+fn rank(entries: &[Entry], query: &str, now: u64) -> Vec<PathBuf> {
+    let query = query.trim().to_lowercase();
+
+    let mut matches = entries.iter()
+        .filter_map(|entry| {
+            let tier = match_tier(&entry.path, &query)?;
+            Some((tier, entry.frecency(now), entry.path.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    matches.sort_by(|(tier_a, score_a, _), (tier_b, score_b, _)| {
+        tier_a.cmp(tier_b)
+            .then(score_b.partial_cmp(score_a)
+                  .unwrap_or(std::cmp::Ordering::Equal))
+    });
+
+    matches.into_iter()
+        .map(|(_, _, path)| path)
+        .collect()
+}
+
+fn import_z() -> WResult<Vec<Entry>> {
+    let db_path = match std::env::var_os("_Z_DATA") {
+        Some(path) => PathBuf::from(path),
+        None => crate::paths::home_path()?.join(".z"),
+    };
+    let db = std::fs::read_to_string(db_path)?;
+
+    Ok(db.lines().filter_map(Entry::parse).collect())
+}
+
+// Lines look like "weight\tpath", but there are no timestamps
+fn import_autojump() -> WResult<Vec<Entry>> {
+    let db_path = dirs_2::data_dir()
+        .ok_or(WError::NoneError)?
+        .join("autojump/autojump.txt");
+    let db = std::fs::read_to_string(db_path)?;
+    let now = now();
+
+    Ok(db.lines()
+       .filter_map(|line| parse_autojump(line, now))
+       .collect())
+}
+
+fn parse_autojump(line: &str, now: u64) -> Option<Entry> {
+    let mut parts = line.splitn(2, '\t');
+    let rank = parts.next()?.trim().parse().ok()?;
+    let path = PathBuf::from(parts.next()?);
+    Some(Entry { path, rank, time: now })
+}
+
+// zoxide's database is binary, so ask zoxide itself for the scores
+fn import_zoxide() -> WResult<Vec<Entry>> {
+    let output = std::process::Command::new("zoxide")
+        .args(["query", "--list", "--score"])
+        .output()?;
+    let db = String::from_utf8_lossy(&output.stdout);
+    let now = now();
+
+    Ok(db.lines()
+       .filter_map(|line| parse_zoxide(line, now))
+       .collect())
+}
+
+// The score is padded with spaces: "  12.5 /some/path"
+fn parse_zoxide(line: &str, now: u64) -> Option<Entry> {
+    let mut parts = line.trim().splitn(2, ' ');
+    let rank = parts.next()?.parse().ok()?;
+    let path = PathBuf::from(parts.next()?.trim());
+    Some(Entry { path, rank, time: now })
+}
+
+// Merges the database of another jumper into ours
+pub fn import(source: &str) -> WResult<usize> {
+    let imported = match source {
+        "zoxide" => import_zoxide()?,
+        "z" => import_z()?,
+        "autojump" => import_autojump()?,
+        _ => return Err(WError::Log(format!("Can't import from {}, try zoxide, z or autojump",
+                                            source)))
+    };
+    let count = imported.len();
+
+    update(|entries| {
+        for entry in imported {
+            add_rank(entries, entry.path, entry.rank, entry.time);
+        }
+        age(entries);
+    })?;
+
+    Ok(count)
+}
+
+#[test]
+fn test_parse_entry() {
+    let entry = Entry::parse("/home/user/a|b|12.5|1600000000").unwrap();
+    assert_eq!(entry.path, PathBuf::from("/home/user/a|b"));
+    assert_eq!(entry.rank, 12.5);
+    assert_eq!(entry.time, 1600000000);
+
+    assert!(Entry::parse("/home/user|x|1600000000").is_none());
+    assert!(Entry::parse("/home/user").is_none());
+    assert!(parse_db("/a|1|2\n\n/b|3|4\n").unwrap().len() == 2);
+    assert!(parse_db("/a|1|2\ngarbage\n").is_err());
+}
+
+#[test]
+fn test_frecency() {
+    let entry = Entry { path: PathBuf::from("/a"), rank: 8.0, time: 1000000 };
+
+    assert_eq!(entry.frecency(1000000), 32.0);
+    assert_eq!(entry.frecency(1000000 + 7200), 16.0);
+    assert_eq!(entry.frecency(1000000 + 2 * 86400), 4.0);
+    assert_eq!(entry.frecency(1000000 + 30 * 86400), 2.0);
+}
+
+#[test]
+fn test_rank() {
+    let now = 1000000;
+    let entry = |path: &str, rank| Entry { path: PathBuf::from(path), rank, time: now };
+    let mut entries = vec![entry("/src/project", 1.0),
+                           entry("/project/src", 5.0),
+                           entry("/work/pr-oj", 9.0),
+                           entry("/home/projects", 2.0)];
+
+    // Name matches first, then the rest of the path, then scattered letters
+    assert_eq!(rank(&entries, "Proj", now),
+               vec![PathBuf::from("/home/projects"),
+                    PathBuf::from("/src/project"),
+                    PathBuf::from("/project/src"),
+                    PathBuf::from("/work/pr-oj")]);
+    assert!(rank(&entries, "xyz", now).is_empty());
+
+    add_rank(&mut entries, PathBuf::from("/src/project"), 2.0, now + 10);
+    add_rank(&mut entries, PathBuf::from("/new"), 1.0, now);
+    assert_eq!(entries[0].rank, 3.0);
+    assert_eq!(entries[0].time, now + 10);
+    assert_eq!(entries.len(), 5);
+
+    entries.push(entry("/big", MAX_RANK));
+    age(&mut entries);
+    assert!(entries.iter().all(|entry| entry.path != Path::new("/new")));
+}
+
+#[test]
+fn test_parse_import() {
+    let entry = parse_autojump("22.4\t/home/user/some dir", 5).unwrap();
+    assert_eq!((entry.path, entry.rank, entry.time),
+               (PathBuf::from("/home/user/some dir"), 22.4, 5));
+
+    let entry = parse_zoxide("   4.5 /home/user/some dir", 5).unwrap();
+    assert_eq!((entry.path, entry.rank), (PathBuf::from("/home/user/some dir"), 4.5));
+
+    assert!(parse_autojump("/no/weight", 5).is_none());
+    assert!(parse_zoxide("", 5).is_none());
+}
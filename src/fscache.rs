@@ -16,16 +16,16 @@ pub type CachedFiles = (Option<File>, Async<Files>);
 
 #[derive(Debug, Clone)]
 pub struct DirSettings {
-    sort: SortBy,
-    dirs_first: bool,
-    reverse: bool,
-    show_hidden: bool,
-    filter: Option<String>,
-    filter_selected: bool,
+    pub sort: SortBy,
+    pub dirs_first: bool,
+    pub reverse: bool,
+    pub show_hidden: bool,
+    pub filter: Option<String>,
+    pub filter_selected: bool,
 }
 
 impl DirSettings {
-    fn new() -> DirSettings {
+    pub fn new() -> DirSettings {
         DirSettings {
             sort: SortBy::Name,
             dirs_first: true,
@@ -39,13 +39,13 @@ impl DirSettings {
 
 #[derive(Debug, Clone)]
 pub struct TabSettings {
-    selection: Option<File>,
-    multi_selections: Vec<File>,
-    dir_settings: DirSettings,
+    pub selection: Option<File>,
+    pub multi_selections: Vec<File>,
+    pub dir_settings: DirSettings,
 }
 
 impl TabSettings {
-    fn new() -> TabSettings {
+    pub fn new() -> TabSettings {
         TabSettings {
             selection: None,
             multi_selections: vec![],
@@ -11,6 +11,10 @@ pub struct HBox<T: Widget> {
     pub ratios: Option<Vec<usize>>,
     pub zoom_active: bool,
     pub active: Option<usize>,
+    // Widgets to show from left to right, all of them if None
+    pub order: Option<Vec<usize>>,
+    // Stack widgets from top to bottom instead
+    pub vertical: bool,
 }
 
 impl<T> HBox<T>
@@ -24,6 +28,8 @@ where
             ratios: None,
             zoom_active: false,
             active: None,
+            order: None,
+            vertical: false,
         }
     }
 
@@ -44,8 +50,12 @@ where
 
         let coords: Vec<Coordinates> = self.calculate_coordinates()?;
 
-        for (widget, coord) in self.widgets.iter_mut().zip(coords.iter()) {
-            widget.set_coordinates(coord).log();
+        for (index, coord) in self.visible_widgets().into_iter().zip(coords.iter()) {
+            self.widgets
+                .get_mut(index)
+                .ok_or(WError::NoneError)?
+                .set_coordinates(coord)
+                .log();
         }
 
         Ok(())
@@ -87,8 +97,19 @@ where
         self.ratios = Some(ratios);
     }
 
+    pub fn set_order(&mut self, order: Option<Vec<usize>>) {
+        self.order = order;
+    }
+
+    pub fn visible_widgets(&self) -> Vec<usize> {
+        match self.order.clone() {
+            Some(order) => order,
+            None => (0..self.widgets.len()).collect(),
+        }
+    }
+
     pub fn calculate_equal_ratios(&self) -> WResult<Vec<usize>> {
-        let len = self.widgets.len();
+        let len = self.visible_widgets().len();
         if len == 0 {
             return WError::no_widget();
         }
@@ -102,6 +123,11 @@ where
         let box_xsize = box_coords.xsize();
         let box_ysize = box_coords.ysize();
         let box_top = box_coords.top().y();
+        let box_left = box_coords.top().x();
+        let box_len = match self.vertical {
+            true => box_ysize,
+            false => box_xsize,
+        };
 
         let ratios = match self.ratios.clone() {
             Some(ratios) => ratios,
@@ -112,13 +138,13 @@ where
 
         let mut ratios = ratios
             .iter()
-            .map(|&r| (r as f64 * box_xsize as f64 / ratios_sum as f64).round() as usize)
+            .map(|&r| (r as f64 * box_len as f64 / ratios_sum as f64).round() as usize)
             .map(|r| if r < 10 { 10 } else { r })
             .collect::<Vec<_>>();
 
         let mut ratios_sum: usize = ratios.iter().sum();
 
-        while ratios_sum + ratios.len() > box_xsize as usize + 1 {
+        while ratios_sum + ratios.len() > box_len as usize + 1 {
             let ratios_max = ratios
                 .iter()
                 .position(|&r| r == *ratios.iter().max().unwrap())
@@ -133,21 +159,30 @@ where
                 let len = coords.len();
                 let gap = if len == ratios.len() { 0 } else { 1 };
 
-                let widget_xsize = *ratio as u16;
-                let widget_xpos = if len == 0 {
-                    box_coords.top().x()
-                } else {
-                    let prev_coords = coords.last().unwrap();
-                    let prev_xsize = prev_coords.xsize();
-                    let prev_xpos = prev_coords.position().x();
+                let widget_len = *ratio as u16;
+                let widget_pos = match (coords.last(), self.vertical) {
+                    (None, true) => box_top,
+                    (None, false) => box_left,
+                    (Some(prev_coords), true) => {
+                        prev_coords.ysize() + prev_coords.position().y() + gap
+                    }
+                    (Some(prev_coords), false) => {
+                        prev_coords.xsize() + prev_coords.position().x() + gap
+                    }
+                };
 
-                    prev_xsize + prev_xpos + gap
+                let (size, position) = match self.vertical {
+                    true => (
+                        Size(box_xsize, widget_len),
+                        Position(box_left, widget_pos),
+                    ),
+                    false => (
+                        Size(widget_len, box_ysize),
+                        Position(widget_pos, box_top),
+                    ),
                 };
 
-                coords.push(Coordinates {
-                    size: Size(widget_xsize, box_ysize),
-                    position: Position(widget_xpos, box_top),
-                });
+                coords.push(Coordinates { size, position });
                 coords
             });
 
@@ -218,8 +253,9 @@ where
         }
 
         Ok(self
-            .widgets
-            .iter()
+            .visible_widgets()
+            .into_iter()
+            .filter_map(|index| self.widgets.get(index))
             .map(|child| {
                 child
                     .get_drawlist()
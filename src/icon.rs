@@ -1,14 +1,221 @@
 // Stolen from lsd: https://github.com/Peltoche/lsd
 // Apache License 2.0
 
-use std::path::PathBuf;
+use std::path::Path;
 use std::collections::HashMap;
 
+use ini::Ini;
+
+use crate::fail::{ErrorLog, KeyBindError, WError, WResult};
+
 pub struct Icons {
     icons_by_name: HashMap<&'static str, &'static str>,
     icons_by_extension: HashMap<&'static str, &'static str>,
     default_folder_icon: &'static str,
     default_file_icon: &'static str,
+    user_icons: UserIcons,
+}
+
+#[derive(Clone, Debug)]
+pub struct Icon {
+    pub icon: String,
+    pub color: Option<String>,
+}
+
+// Describes the entry an icon is looked up for
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IconState {
+    pub dir: bool,
+    pub open: bool,
+    pub symlink: bool,
+    pub executable: bool,
+}
+
+// Icons from the "icons" file in the config dir. These take
+// precedence over the built-in tables. Example:
+//
+// [name]
+// Makefile = \u{e779} yellow
+// [extension]
+// proj = \u{f1b2} #ff8800
+// [glob]
+// *.test.js = \u{f0c3} green
+// [mime]
+// image/* = \u{f1c5}
+// [special]
+// directory = \u{f114}
+// directory_open = \u{f115}
+// file = \u{f016}
+// symlink = \u{f481}
+// symlink_directory = \u{f482}
+// executable = \u{f489} light_green
+#[derive(Default, Debug)]
+struct UserIcons {
+    by_name: HashMap<String, Icon>,
+    by_extension: HashMap<String, Icon>,
+    by_glob: Vec<(String, Icon)>,
+    by_mime: Vec<(String, Icon)>,
+    directory: Option<Icon>,
+    directory_open: Option<Icon>,
+    file: Option<Icon>,
+    symlink: Option<Icon>,
+    symlink_directory: Option<Icon>,
+    executable: Option<Icon>,
+}
+
+impl Icon {
+    // Parses "ICON [COLOR]", ICON can be given literally or as \u{XXXX}
+    fn parse(value: &str) -> WResult<Icon> {
+        let mut parts = value.split_whitespace();
+        let icon = parts.next()
+            .ok_or_else(|| WError::ConfigLineError(value.to_string()))?;
+        let icon = Icon::unescape(icon)
+            .ok_or_else(|| WError::ConfigLineError(value.to_string()))?;
+
+        let color = match parts.next() {
+            Some(color) => Some(crate::term::parse_color(color)
+                                .ok_or_else(|| WError::ConfigLineError(value.to_string()))?),
+            None => None,
+        };
+
+        Ok(Icon { icon, color })
+    }
+
+    fn unescape(icon: &str) -> Option<String> {
+        if !icon.contains("\\u") {
+            return Some(icon.to_string());
+        }
+
+        let mut unescaped = String::new();
+        let mut rest = icon;
+
+        while let Some(pos) = rest.find("\\u") {
+            unescaped.push_str(&rest[..pos]);
+            rest = &rest[pos + 2..];
+
+            let (hex, remaining) = match rest.strip_prefix('{') {
+                Some(braced) => {
+                    let end = braced.find('}')?;
+                    (&braced[..end], &braced[end + 1..])
+                }
+                None => {
+                    let end = rest.len().min(4);
+                    rest.split_at(end)
+                }
+            };
+
+            let ch = u32::from_str_radix(hex, 16).ok()
+                .and_then(std::char::from_u32)?;
+            unescaped.push(ch);
+            rest = remaining;
+        }
+
+        unescaped.push_str(rest);
+        Some(unescaped)
+    }
+}
+
+impl UserIcons {
+    fn load() -> WResult<UserIcons> {
+        let icons_path = crate::paths::icons_path()?;
+
+        if !icons_path.exists() {
+            return Ok(UserIcons::default());
+        }
+
+        let ini = Ini::load_from_file_noescape(icons_path)
+            .map_err(KeyBindError::from)?;
+        let mut user_icons = UserIcons::default();
+
+        for (section, props) in ini.iter() {
+            for (key, value) in props.iter() {
+                let icon = match Icon::parse(value) {
+                    Ok(icon) => icon,
+                    Err(_) => {
+                        let line = format!("{} = {}", key, value);
+                        WError::config_error::<()>(line).log();
+                        continue;
+                    }
+                };
+
+                match (section, key) {
+                    (Some("name"), name) => {
+                        user_icons.by_name.insert(name.to_string(), icon);
+                    }
+                    (Some("extension"), ext) => {
+                        let ext = ext.trim_start_matches('.').to_lowercase();
+                        user_icons.by_extension.insert(ext, icon);
+                    }
+                    (Some("glob"), glob) => {
+                        user_icons.by_glob.push((glob.to_string(), icon))
+                    }
+                    (Some("mime"), mime) => {
+                        user_icons.by_mime.push((mime.to_string(), icon))
+                    }
+                    (Some("special"), "directory") => user_icons.directory = Some(icon),
+                    (Some("special"), "directory_open") => user_icons.directory_open = Some(icon),
+                    (Some("special"), "file") => user_icons.file = Some(icon),
+                    (Some("special"), "symlink") => user_icons.symlink = Some(icon),
+                    (Some("special"), "symlink_directory") => {
+                        user_icons.symlink_directory = Some(icon)
+                    }
+                    (Some("special"), "executable") => user_icons.executable = Some(icon),
+                    _ => {
+                        let line = format!("{} = {}", key, value);
+                        WError::config_error::<()>(line).log();
+                    }
+                }
+            }
+        }
+
+        Ok(user_icons)
+    }
+
+    fn get_by_path(&self, file_name: &str, path: &Path) -> Option<&Icon> {
+        if let Some(icon) = self.by_name.get(file_name) {
+            return Some(icon);
+        }
+
+        let glob = self.by_glob
+            .iter()
+            .find(|(glob, _)| crate::shell::glob_match(glob, file_name));
+        if let Some((_, icon)) = glob {
+            return Some(icon);
+        }
+
+        let extension = path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        if let Some(icon) = extension.and_then(|ext| self.by_extension.get(&ext)) {
+            return Some(icon);
+        }
+
+        if self.by_mime.is_empty() {
+            return None;
+        }
+
+        // Only guess by extension, sniffing file contents would be
+        // way too slow to do while drawing
+        let mime = mime_guess::from_path(path).first()?;
+        let mime = mime.essence_str();
+        self.by_mime
+            .iter()
+            .find(|(pattern, _)| crate::shell::glob_match(pattern, mime))
+            .map(|(_, icon)| icon)
+    }
+
+    fn get_by_state(&self, state: IconState) -> Option<&Icon> {
+        match state {
+            IconState { symlink: true, dir: true, .. } if self.symlink_directory.is_some() => {
+                self.symlink_directory.as_ref()
+            }
+            IconState { symlink: true, .. } if self.symlink.is_some() => {
+                self.symlink.as_ref()
+            }
+            IconState { dir: true, open: true, .. } => self.directory_open.as_ref(),
+            IconState { dir: false, executable: true, .. } => self.executable.as_ref(),
+            _ => None,
+        }
+    }
 }
 
 // In order to add a new icon, write the unicode value like "\ue5fb" then
@@ -25,15 +232,29 @@ impl Icons {
                                      "\u{f016}",  // 
                                      "\u{f115}"); // 
 
+        let user_icons = UserIcons::load()
+            .log_and()
+            .unwrap_or_default();
+
         Self {
             icons_by_name,
             icons_by_extension,
             default_file_icon,
             default_folder_icon,
+            user_icons,
         }
     }
 
-    pub fn get(&self, name: &PathBuf) -> &'static str {
+    pub fn get(&self, name: &Path) -> &str {
+        let state = IconState {
+            dir: name.is_dir(),
+            ..IconState::default()
+        };
+
+        self.get_with_state(name, state).0
+    }
+
+    pub fn get_with_state(&self, name: &Path, state: IconState) -> (&str, Option<&str>) {
         let file_name = name.file_name()
             .and_then(|name| name.to_str())
             .unwrap_or("");
@@ -41,24 +262,41 @@ impl Icons {
         let extension = name.extension()
             .and_then(|ext| ext.to_str());
 
+        let user_icon = self.user_icons
+            .get_by_path(file_name, name)
+            .or_else(|| self.user_icons.get_by_state(state));
+
+        if let Some(Icon { icon, color }) = user_icon {
+            return (icon, color.as_deref());
+        }
+
         // Check the known names.
         if let Some(icon) = self.icons_by_name.get(file_name) {
-            return icon;
+            return (icon, None);
         }
 
         // Check the known extensions.
         if let Some(extension) = extension {
             if let Some(icon) = self.icons_by_extension.get(extension) {
-                return icon;
+                return (icon, None);
             }
         }
 
-        if name.is_dir() {
-            return self.default_folder_icon;
+        let user_default = match state.dir {
+            true => self.user_icons.directory.as_ref(),
+            false => self.user_icons.file.as_ref(),
+        };
+
+        if let Some(Icon { icon, color }) = user_default {
+            return (icon, color.as_deref());
+        }
+
+        if state.dir {
+            return (self.default_folder_icon, None);
         }
 
         // Use the default icons.
-        return self.default_file_icon;
+        (self.default_file_icon, None)
     }
 
     fn get_default_icons_by_name() -> HashMap<&'static str, &'static str> {
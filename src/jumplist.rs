@@ -0,0 +1,134 @@
+use crate::fail::WResult;
+use crate::files::{File, Files};
+use crate::keybind::{Bindings, JumpListAction, KeyBinds};
+use crate::listview::ListView;
+use crate::pathlist::{Outcome, PathList, PathListView};
+
+// Older entries fall off the end
+const MAX_JUMPS: usize = 100;
+
+#[derive(Clone, Debug)]
+pub struct Jump {
+    pub dir: File,
+    // Where the cursor was when the directory was left
+    pub selection: Option<File>,
+}
+
+// Every directory a tab has been in, like a browser history or the
+// jumplist in vim
+#[derive(Clone, Debug)]
+pub struct JumpList {
+    jumps: Vec<Jump>,
+    pos: usize,
+}
+
+impl JumpList {
+    pub fn new(dir: &File) -> JumpList {
+        JumpList {
+            jumps: vec![Jump { dir: dir.clone(), selection: None }],
+            pos: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.jumps.len()
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    // Going somewhere new drops everything ahead of the current position.
+    // Nothing changes when going back or forward, that already moved.
+    pub fn visit(&mut self, dir: &File, selection: Option<File>) {
+        let current = &mut self.jumps[self.pos];
+        if &current.dir == dir {
+            return;
+        }
+        current.selection = selection;
+
+        self.jumps.truncate(self.pos + 1);
+        self.jumps.push(Jump { dir: dir.clone(), selection: None });
+
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+
+        self.pos = self.jumps.len() - 1;
+    }
+
+    // Moves to pos, the cursor is remembered for the entry left behind
+    pub fn jump_to(&mut self, pos: usize, selection: Option<File>) -> Option<Jump> {
+        if pos >= self.jumps.len() || pos == self.pos {
+            return None;
+        }
+
+        self.jumps[self.pos].selection = selection;
+        self.pos = pos;
+        Some(self.jumps[pos].clone())
+    }
+
+    pub fn back(&mut self, selection: Option<File>) -> Option<Jump> {
+        let pos = self.pos.checked_sub(1)?;
+        self.jump_to(pos, selection)
+    }
+
+    pub fn forward(&mut self, selection: Option<File>) -> Option<Jump> {
+        self.jump_to(self.pos + 1, selection)
+    }
+}
+
+// Lists the jumps, most recent first
+pub type JumpListView = PathListView<JumpList>;
+
+impl PathList for JumpList {
+    type Action = JumpListAction;
+    // Position of the picked entry in the JumpList
+    type Pick = usize;
+
+    fn files(&self) -> WResult<Vec<File>> {
+        Ok(self.jumps
+           .iter()
+           .rev()
+           .map(|jump| {
+               let mut file = jump.dir.clone();
+               file.name = match &jump.selection {
+                   Some(selection) => format!("{} ({})",
+                                              file.short_string(),
+                                              selection.name),
+                   None => file.short_string(),
+               };
+               file
+           })
+           .collect())
+    }
+
+    fn header(&self, len: usize) -> String {
+        format!("Jump list: {} locations", len)
+    }
+
+    fn bindings(keybinds: KeyBinds) -> Bindings<JumpListAction> {
+        keybinds.jumplist
+    }
+
+    fn goto_action() -> JumpListAction {
+        JumpListAction::Goto
+    }
+
+    fn selection(&self) -> usize {
+        self.len() - 1 - self.pos
+    }
+
+    fn do_action(&mut self,
+                 action: &JumpListAction,
+                 list: &ListView<Files>) -> WResult<Outcome<usize>> {
+        use JumpListAction::*;
+
+        let outcome = match action {
+            Close => Outcome::Close,
+            Goto => Outcome::Pick(self.len() - 1 - list.get_selection()),
+        };
+
+        Ok(outcome)
+    }
+}
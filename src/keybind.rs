@@ -13,19 +13,205 @@ use crate::widget::Widget;
 pub type KbResult<T> = Result<T, KeyBindError>;
 
 #[derive(Clone, Debug)]
-pub struct Bindings<T>(HashMap<AnyKey, T>);
+pub struct Bindings<T> {
+    keys: HashMap<AnyKey, T>,
+    // Two or more keys, like "gg"
+    sequences: Vec<(Vec<AnyKey>, T)>,
+}
 
 impl<T> Bindings<T> {
     pub fn get(&self, key: impl Into<AnyKey>) -> Option<&T> {
-        self.0.get(&key.into())
+        self.keys.get(&key.into())
     }
 
     pub fn insert(&mut self, key: impl Into<AnyKey>, value: T) -> Option<T> {
-        self.0.insert(key.into(), value)
+        self.keys.insert(key.into(), value)
+    }
+
+    pub fn insert_sequence(&mut self, keys: Vec<AnyKey>, value: T) {
+        self.sequences.retain(|(bound, _)| bound != &keys);
+        self.sequences.push((keys, value));
     }
 
     pub fn new() -> Self {
-        Bindings(HashMap::new())
+        Bindings {
+            keys: HashMap::new(),
+            sequences: vec![],
+        }
+    }
+
+    // Single keys and sequences alike
+    pub fn iter(&self) -> impl Iterator<Item = (Vec<AnyKey>, &T)> {
+        let singles = self.keys.iter().map(|(key, bound)| (vec![*key], bound));
+        let sequences = self.sequences.iter().map(|(keys, bound)| (keys.clone(), bound));
+        singles.chain(sequences)
+    }
+
+    // Some sequence goes on after these keys
+    pub fn is_prefix(&self, keys: &[Key]) -> bool {
+        self.sequences
+            .iter()
+            .any(|(bound, _)| bound.len() > keys.len() && keys_match(&bound[..keys.len()], keys))
+    }
+}
+
+fn keys_match(bound: &[AnyKey], keys: &[Key]) -> bool {
+    bound.len() == keys.len()
+        && bound.iter().zip(keys).all(|(bound, key)| {
+            *bound == AnyKey::Key(*key) || AnyKey::from(*key).any() == Some(*bound)
+        })
+}
+
+// Written with spaces between the keys, like in the keys file
+pub fn keys_to_string(keys: &[AnyKey]) -> String {
+    keys.iter()
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl<T: Display> Bindings<T> {
+    // What can follow keys, with the action the sequence ends in
+    pub fn continuations(&self, keys: &[Key], describe: impl Fn(&T) -> String) -> Vec<(String, String)> {
+        self.sequences
+            .iter()
+            .filter(|(bound, _)| bound.len() > keys.len() && keys_match(&bound[..keys.len()], keys))
+            .map(|(bound, action)| (keys_to_string(&bound[keys.len()..]), describe(action)))
+            .collect()
+    }
+
+    // All keys and sequences bound to action, whatever its parameter
+    pub fn keys(&self, action: &T) -> Vec<Vec<AnyKey>> {
+        let name = action.to_string();
+        self.keys_where(|bound| bound.to_string() == name)
+    }
+
+    pub fn keys_where(&self, pred: impl Fn(&T) -> bool) -> Vec<Vec<AnyKey>> {
+        let mut keys = self
+            .iter()
+            .filter(|(_, bound)| pred(bound))
+            .map(|(keys, _)| keys)
+            .collect::<Vec<_>>();
+
+        keys.sort_by_key(|keys| {
+            let keys = keys_to_string(keys);
+            (keys.len(), keys)
+        });
+        keys
+    }
+}
+
+impl<T> Bindings<T>
+where
+    T: BindingSection,
+    Bindings<T>: Default,
+{
+    // Falls back to placeholder bindings like "_", with the key inserted
+    pub fn get_any(&self, key: Key) -> Option<T> {
+        match self.get(key) {
+            Some(action) => Some(*action),
+            None => AnyKey::from(key)
+                .any()
+                .and_then(|any_key| self.get(any_key))
+                .map(|action| action.insert_key_param(key)),
+        }
+    }
+
+    // Placeholders take the key they stand for, like in get_any
+    pub fn get_sequence(&self, keys: &[Key]) -> Option<T> {
+        let (bound, action) = self
+            .sequences
+            .iter()
+            .find(|(bound, _)| keys_match(bound, keys))?;
+
+        Some(bound
+             .iter()
+             .zip(keys)
+             .filter(|(bound, _)| !matches!(bound, AnyKey::Key(_)))
+             .fold(*action, |action, (_, key)| action.insert_key_param(*key)))
+    }
+}
+
+// The start of a key sequence, shared by all widgets just like the count.
+// Every widget a key gets passed to can continue it.
+#[derive(Clone, Debug, Default)]
+pub struct PendingKeys {
+    // Counts key events, a sequence only goes on with the very next one
+    event: usize,
+    keys: Vec<Key>,
+    // The event that typed the last of the keys
+    last_event: usize,
+    // Timed out or went nowhere, the next key has to end it
+    finishing: bool,
+    // The rest of each sequence that can follow, with its action
+    hints: Vec<(String, String)>,
+    // Screen rows taken by the hints when they were drawn last
+    pub hint_rows: usize,
+}
+
+impl PendingKeys {
+    pub fn next_event(&mut self) -> usize {
+        self.event += 1;
+        self.event
+    }
+
+    // Typed before the current key, as long as that still counts
+    pub fn keys(&self) -> &[Key] {
+        match self.finishing || self.last_event + 1 == self.event {
+            true => &self.keys,
+            false => &[],
+        }
+    }
+
+    pub fn is_finishing(&self) -> bool {
+        self.finishing
+    }
+
+    pub fn hints(&self) -> &[(String, String)] {
+        match self.keys().is_empty() {
+            true => &[],
+            false => &self.hints,
+        }
+    }
+
+    // Returns the event the timeout has to check for
+    pub fn push(&mut self, key: Key, hints: Vec<(String, String)>) -> usize {
+        let mut keys = self.keys().to_vec();
+        keys.push(key);
+
+        self.keys = keys;
+        self.hints = hints;
+        self.last_event = self.event;
+        self.finishing = false;
+        self.event
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.finishing = false;
+    }
+
+    // Nothing was typed since event
+    pub fn timed_out(&self, event: usize) -> bool {
+        !self.keys.is_empty() && self.event == event && self.last_event == event
+    }
+
+    // Nothing took the key typed after the sequence
+    pub fn went_nowhere(&self) -> bool {
+        !self.keys.is_empty() && !self.finishing && self.last_event + 1 == self.event
+    }
+
+    // All keys of the sequence, to go through the widgets again
+    pub fn take_keys(&mut self) -> Vec<Key> {
+        self.hints.clear();
+        std::mem::take(&mut self.keys)
+    }
+
+    // The next key can only end the sequence after prefix now, so a key
+    // bound alone and as a prefix does its own thing
+    pub fn finish(&mut self, prefix: &[Key]) {
+        self.keys = prefix.to_vec();
+        self.finishing = true;
     }
 }
 
@@ -44,28 +230,132 @@ where
         Err(KeyBindError::MovementUndefined)?
     }
 
+    // Called instead of movement() when a count was typed first
+    fn movement_count(&mut self, movement: &Movement, count: usize) -> WResult<()> {
+        match movement.with_count(count) {
+            Some(movement) => self.movement(&movement),
+            None => {
+                for _ in 0..count {
+                    self.movement(movement)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Called instead of do_action() when a count was typed first
+    fn do_action_count(&mut self, action: &Self::Action, count: usize) -> WResult<()> {
+        for _ in 0..count {
+            self.do_action(action)?;
+        }
+        Ok(())
+    }
+
+    // Name of the action in the key hints
+    fn describe(&self, action: &Self::Action) -> String {
+        action.to_string()
+    }
+
+    // Widgets that collect counts like the 5 in "5j" return true
+    fn takes_count(&self, _key: Key) -> bool {
+        false
+    }
+
+    // Runs the movement unless the widget can't move
+    fn try_movement(&mut self, movement: &Movement) -> Option<WResult<()>> {
+        let result = match self.get_core().ok()?.get_count() {
+            Some(count) => self.movement_count(movement, count),
+            None => self.movement(movement),
+        };
+
+        match result {
+            Err(WError::KeyBind(KeyBindError::MovementUndefined)) => None,
+            result => {
+                self.get_core().ok()?.take_count();
+                Some(result)
+            }
+        }
+    }
+
+    fn run_action(&mut self, action: &Self::Action) -> WResult<()> {
+        match self.get_core()?.take_count() {
+            Some(count) => self.do_action_count(action, count),
+            None => self.do_action(action),
+        }
+    }
+
+    // Either starts a sequence or goes on with one typed before
+    fn sequence_key(&mut self, key: Key) -> Option<WResult<()>> {
+        let core = self.get_core().ok()?;
+        let (mut keys, finishing) = {
+            let pending = core.pending_keys.lock();
+            (pending.keys().to_vec(), pending.is_finishing())
+        };
+        keys.push(key);
+
+        let movement = core.config().keybinds.movement;
+        let bindings = self.search_in();
+
+        if keys.len() > 1 {
+            if let Some(movement) = movement.get_sequence(&keys) {
+                if let Some(result) = self.try_movement(&movement) {
+                    self.get_core().ok()?.pending_keys.lock().clear();
+                    return Some(result);
+                }
+            }
+
+            if let Some(action) = bindings.get_sequence(&keys) {
+                self.get_core().ok()?.pending_keys.lock().clear();
+                return Some(self.run_action(&action));
+            }
+        }
+
+        // Typing into a placeholder like InsertChar(_) never starts moving
+        let types = keys.len() == 1 && bindings.get(key).is_none() && bindings.get_any(key).is_some();
+
+        if !finishing && (bindings.is_prefix(&keys) || !types && movement.is_prefix(&keys)) {
+            let mut hints = bindings.continuations(&keys, |action| self.describe(action));
+            if !types {
+                hints.extend(movement.continuations(&keys, Movement::to_string));
+            }
+
+            return Some(self.get_core().ok()?.push_pending_key(key, hints));
+        }
+
+        match keys.len() {
+            1 => None,
+            // Some widget further in might know the sequence
+            _ => Some(WError::undefined_key(key)),
+        }
+    }
+
     fn do_key(&mut self, key: Key) -> WResult<()> {
         let gkey = AnyKey::from(key);
+        let count = self.get_core()?.get_count();
+
+        if let Some(result) = self.sequence_key(key) {
+            return result;
+        }
 
         // Moving takes priority
         if let Some(movement) = self.get_core()?.config().keybinds.movement.get(gkey) {
-            match self.movement(movement) {
-                Ok(()) => return Ok(()),
-                Err(WError::KeyBind(KeyBindError::MovementUndefined)) => {}
-                Err(e) => Err(e)?,
+            if let Some(result) = self.try_movement(movement) {
+                return result;
             }
         }
 
-        self.search_in();
-
         let bindings = self.search_in();
 
-        if let Some(action) = bindings.get(key) {
-            return self.do_action(action);
-        } else if let Some(any_key) = gkey.any() {
-            if let Some(action) = bindings.get(any_key) {
-                let action = action.insert_key_param(key);
-                return self.do_action(&action);
+        if let Some(action) = bindings.get_any(key) {
+            return self.run_action(&action);
+        }
+
+        if let Key::Char(digit @ '0'..='9') = key {
+            let digit = digit.to_digit(10).unwrap_or(0) as usize;
+
+            // A leading 0 isn't a count
+            if (digit != 0 || count.is_some()) && self.takes_count(key) {
+                return self.get_core()?.push_count_digit(digit);
             }
         }
 
@@ -86,6 +376,15 @@ pub struct KeyBinds {
     pub fold: Bindings<FoldAction>,
     pub log: Bindings<LogAction>,
     pub quickaction: Bindings<QuickActionAction>,
+    pub basket: Bindings<BasketAction>,
+    pub tag: Bindings<TagAction>,
+    pub tagged: Bindings<TaggedAction>,
+    pub jumplist: Bindings<JumpListAction>,
+    pub completion: Bindings<CompletionAction>,
+    pub historysearch: Bindings<HistorySearchAction>,
+    pub palette: Bindings<PaletteAction>,
+    // Run by FileBrowserAction::RunCommand, the number is the position here
+    pub commands: Vec<UserCommand>,
 }
 
 impl Default for KeyBinds {
@@ -102,17 +401,35 @@ impl Default for KeyBinds {
             fold: Bindings::default(),
             log: Bindings::default(),
             quickaction: Bindings::default(),
+            basket: Bindings::default(),
+            tag: Bindings::default(),
+            tagged: Bindings::default(),
+            jumplist: Bindings::default(),
+            completion: Bindings::default(),
+            historysearch: Bindings::default(),
+            palette: Bindings::default(),
+            commands: vec![],
         }
     }
 }
 
 impl KeyBinds {
+    // Commands from the [commands] section go by their command line
+    pub fn filebrowser_name(&self, action: &FileBrowserAction) -> String {
+        match action {
+            FileBrowserAction::RunCommand(n) => self.commands.get(*n)
+                .map(|command| command.cmd.clone())
+                .unwrap_or_else(|| action.to_string()),
+            _ => action.to_string(),
+        }
+    }
+
     pub fn load() -> WResult<KeyBinds> {
         let bindings_path = crate::paths::bindings_path()?;
         let ini = Ini::load_from_file_noescape(bindings_path).map_err(KeyBindError::from)?;
 
         let movement = Movement::load_section(&ini);
-        let filebrowser = FileBrowserAction::load_section(&ini);
+        let mut filebrowser = FileBrowserAction::load_section(&ini);
         let filelist = FileListAction::load_section(&ini);
         let tab = TabAction::load_section(&ini);
         let media = MediaAction::load_section(&ini);
@@ -122,6 +439,14 @@ impl KeyBinds {
         let fold = FoldAction::load_section(&ini);
         let log = LogAction::load_section(&ini);
         let quickaction = QuickActionAction::load_section(&ini);
+        let basket = BasketAction::load_section(&ini);
+        let tag = TagAction::load_section(&ini);
+        let tagged = TaggedAction::load_section(&ini);
+        let jumplist = JumpListAction::load_section(&ini);
+        let completion = CompletionAction::load_section(&ini);
+        let historysearch = HistorySearchAction::load_section(&ini);
+        let palette = PaletteAction::load_section(&ini);
+        let commands = load_commands(&ini, &mut filebrowser);
 
         Ok(KeyBinds {
             movement,
@@ -135,10 +460,100 @@ impl KeyBinds {
             fold,
             log,
             quickaction,
+            basket,
+            tag,
+            tagged,
+            jumplist,
+            completion,
+            historysearch,
+            palette,
+            commands,
         })
     }
 }
 
+// A shell command from the [commands] section
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserCommand {
+    pub cmd: String,
+    // Runs in the terminal instead of the background
+    pub fg: bool,
+    // Keeps the output on the screen until a key is pressed
+    pub wait: bool,
+    // Reads the directory again once the command is done
+    pub refresh: bool,
+    // Asks for the text "$i" stands for
+    pub prompt: bool,
+}
+
+impl FromStr for UserCommand {
+    type Err = KeyBindError;
+
+    // Options come first, like "(fg, wait) make $f:r"
+    fn from_str(command: &str) -> KbResult<UserCommand> {
+        let command = command.trim();
+        let mut user_command = UserCommand {
+            cmd: command.to_string(),
+            fg: false,
+            wait: false,
+            refresh: false,
+            prompt: false,
+        };
+
+        let options = command
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(')'))
+            .map(|(options, cmd)| (options.split(',').map(str::trim).collect::<Vec<_>>(), cmd));
+
+        // Anything else is a subshell, like "(cd build; make)"
+        if let Some((options, cmd)) = options {
+            let known = ["fg", "wait", "refresh", "prompt"];
+            if options.iter().all(|option| known.contains(option)) {
+                user_command.cmd = cmd.trim().to_string();
+                user_command.fg = options.contains(&"fg") || options.contains(&"wait");
+                user_command.wait = options.contains(&"wait");
+                user_command.refresh = options.contains(&"refresh");
+                user_command.prompt = options.contains(&"prompt");
+            }
+        }
+
+        if user_command.cmd.is_empty() {
+            return Err(KeyBindError::WrongCommand(command.to_string()));
+        }
+
+        // Nothing waits for background commands to finish
+        if user_command.refresh && !user_command.fg {
+            return Err(KeyBindError::RefreshInBackground(command.to_string()));
+        }
+
+        Ok(user_command)
+    }
+}
+
+// Keys go on the left here, so commands can contain "=" and ":". They end
+// up in the filebrowser bindings and win over its actions.
+fn load_commands(ini: &Ini, filebrowser: &mut Bindings<FileBrowserAction>) -> Vec<UserCommand> {
+    let section = match ini.section(Some("commands")) {
+        Some(section) => section,
+        None => return vec![],
+    };
+
+    let mut commands = vec![];
+
+    for (keys_str, command_str) in section.iter() {
+        match command_str.parse::<UserCommand>() {
+            Ok(command) => {
+                let action = FileBrowserAction::RunCommand(commands.len());
+                bind_keys(filebrowser, &command.cmd, keys_str, action);
+                commands.push(command);
+            }
+            err @ Err(_) => err.log(),
+        }
+    }
+
+    commands
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum AnyKey {
     Key(Key),
@@ -155,6 +570,10 @@ impl Display for AnyKey {
 
         match self {
             Key(key) => match key {
+                // Named like in the keys file
+                Char('\t') => write!(fmt, "Tab"),
+                Char('\n') => write!(fmt, "Enter"),
+                Char(' ') => write!(fmt, "Space"),
                 Char(ch) => write!(fmt, "{}", ch),
                 Alt(ch) => write!(fmt, "M-{}", ch),
                 Ctrl(ch) => write!(fmt, "C-{}", ch),
@@ -272,6 +691,41 @@ impl FromStr for AnyKey {
     }
 }
 
+fn looks_like_name(word: &str) -> bool {
+    let mut chars = word.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(first), Some(second)) => {
+            word.contains('-') || first.is_uppercase() && second.is_lowercase()
+        }
+        _ => false,
+    }
+}
+
+// Keys of a sequence are separated by spaces, like "g h" or "C-x C-e".
+// Plain characters can also be written together, like "gg".
+pub fn parse_keys(keys: &str) -> KbResult<Vec<AnyKey>> {
+    let mut parsed = vec![];
+
+    for word in keys.split_whitespace() {
+        match word.parse::<AnyKey>() {
+            Ok(key) => parsed.push(key),
+            // Most likely a misspelled key name like "Pageup" or "C-x-e"
+            Err(err) if looks_like_name(word) => return Err(err),
+            Err(err) => {
+                for ch in word.chars() {
+                    parsed.push(ch.to_string().parse().map_err(|_| err.clone())?);
+                }
+            }
+        }
+    }
+
+    match parsed.is_empty() {
+        true => Err(KeyBindError::ParseKeyError(keys.to_string())),
+        false => Ok(parsed),
+    }
+}
+
 #[derive(Copy, Clone, Display, Debug)]
 pub enum CharOrNum {
     Char(char),
@@ -325,6 +779,26 @@ impl FromStr for CharOrNum {
     }
 }
 
+// Binds each of the comma separated keys or sequences in keys_str
+fn bind_keys<T: Copy>(bindings: &mut Bindings<T>, name: &str, keys_str: &str, action: T) {
+    for key_str in keys_str.split(",") {
+        let key_str = key_str.trim();
+
+        let keys = parse_keys(key_str).map_err(|_| {
+            KeyBindError::WrongKey(name.to_string(), key_str.to_string())
+        });
+
+        // If key isn't valid log it and try next binding
+        match keys {
+            Ok(keys) => match keys.as_slice() {
+                [key] => { bindings.insert(*key, action); }
+                keys => bindings.insert_sequence(keys.to_vec(), action),
+            },
+            Err(_) => keys.log(),
+        }
+    }
+}
+
 pub trait BindingSection
 where
     Self: FromStr + Copy + Display + Debug,
@@ -393,21 +867,7 @@ where
                 continue;
             }
 
-            for key_str in keys_str.split(",") {
-                let key_str = key_str.trim();
-
-                let key = key_str.parse::<AnyKey>().map_err(|_| {
-                    KeyBindError::WrongKey(action_str.to_string(), key_str.to_string())
-                });
-
-                // If key isn't valid log it and try next binding
-                if key.is_err() {
-                    key.log();
-                    continue;
-                }
-
-                bindings.insert(key?, action.clone()?);
-            }
+            bind_keys(&mut bindings, action_str, keys_str, action?);
         }
 
         Ok(bindings)
@@ -432,6 +892,17 @@ pub enum Movement {
     PageDown,
 }
 
+impl Movement {
+    // The count replaces the distance bound to Up(n) and Down(n)
+    pub fn with_count(&self, count: usize) -> Option<Movement> {
+        match self {
+            Movement::Up(_) => Some(Movement::Up(count)),
+            Movement::Down(_) => Some(Movement::Down(count)),
+            _ => None,
+        }
+    }
+}
+
 #[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
 pub enum FileBrowserAction {
     LeftColumnDown,
@@ -453,6 +924,25 @@ pub enum FileBrowserAction {
     ToggleColumns,
     ZoomPreview,
     ExecCmd,
+    AddToBasket,
+    ShowBasket,
+    ToggleDualPane,
+    SwitchPane,
+    TogglePanePreview,
+    CopyToOtherPane,
+    MoveToOtherPane,
+    TagWith,
+    SelectTagged,
+    FilterTagged,
+    ShowTagged,
+    JumpFrecent,
+    Back,
+    Forward,
+    ShowJumpList,
+    ShowCommandPalette,
+    ShowHelp,
+    // Only bound through the [commands] section
+    RunCommand(usize),
 }
 
 #[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
@@ -472,6 +962,7 @@ pub enum FileListAction {
     ToNextMtime,
     ToPrevMtime,
     ToggleDirsFirst,
+    VisualMode,
 }
 
 #[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
@@ -481,6 +972,15 @@ pub enum TabAction {
     NextTab,
     PrevTab,
     GotoTab(usize),
+    SaveSession,
+    LoadSession,
+    ListSessions,
+    RenameTab,
+    MoveTabLeft,
+    MoveTabRight,
+    DuplicateTab,
+    TogglePinTab,
+    CommandLine,
 }
 
 #[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
@@ -529,6 +1029,20 @@ pub enum MiniBufferAction {
     DeleteWord,
     CursorToStart,
     CursorToEnd,
+    ForwardWord,
+    BackwardWord,
+    KillLine,
+    KillWord,
+    Yank,
+    YankPop,
+    TransposeChars,
+    TransposeWords,
+    Undo,
+    UpcaseWord,
+    DowncaseWord,
+    CapitalizeWord,
+    EditInEditor,
+    HistorySearch,
 }
 
 #[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
@@ -547,6 +1061,16 @@ pub enum QuickActionAction {
     SelectOrRun(char),
 }
 
+#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+pub enum BasketAction {
+    Close,
+    Goto,
+    Remove,
+    Clear,
+    QuickActions,
+    ExecCmd,
+}
+
 impl BindingSection for Movement {
     fn section() -> &'static str {
         "movement"
@@ -637,6 +1161,24 @@ impl Default for Bindings<FileBrowserAction> {
                 ToggleColumns => Char('c'),
                 ZoomPreview => Char('C'),
                 ExecCmd => Char('!'),
+                AddToBasket => Char('y'),
+                ShowBasket => Char('Y'),
+                ToggleDualPane => Char('|'),
+                SwitchPane => Char('\t'),
+                TogglePanePreview => Char('P'),
+                CopyToOtherPane => Char('p'),
+                MoveToOtherPane => Char('m'),
+                TagWith => Char('"'),
+                SelectTagged => Alt('t'),
+                FilterTagged => Alt('T'),
+                ShowTagged => Char('T'),
+                JumpFrecent => Char('Z'),
+                Back => Char('H'),
+                Forward => Char('L'),
+                ShowJumpList => Ctrl('o'),
+                ShowCommandPalette => Alt('x'),
+                ShowHelp => Char('?'),
+                RunCommand(_) => continue,
             };
 
             filebrowser.insert(key, action.as_default());
@@ -676,6 +1218,7 @@ impl Default for Bindings<FileListAction> {
                 ToNextMtime => Char('K'),
                 ToPrevMtime => Char('k'),
                 ToggleDirsFirst => Char('d'),
+                VisualMode => Char('x'),
             };
 
             filelist.insert(key, action.as_default());
@@ -705,6 +1248,15 @@ impl Default for Bindings<TabAction> {
                 PrevTab => BackTab.into(),
                 CloseTab => Ctrl('w').into(),
                 GotoTab(_) => AnyKey::AnyF,
+                SaveSession => Alt('w').into(),
+                LoadSession => Alt('o').into(),
+                ListSessions => Alt('l').into(),
+                RenameTab => Alt('r').into(),
+                MoveTabLeft => Alt('H').into(),
+                MoveTabRight => Alt('L').into(),
+                DuplicateTab => Alt('d').into(),
+                TogglePinTab => Alt('P').into(),
+                CommandLine => Char(':').into(),
             };
 
             tab.insert(key, action.as_default());
@@ -873,12 +1425,29 @@ impl Default for Bindings<MiniBufferAction> {
                 DeleteWord => Ctrl('h').into(),
                 CursorToStart => Ctrl('a').into(),
                 CursorToEnd => Ctrl('e').into(),
+                ForwardWord => Alt('f').into(),
+                BackwardWord => Alt('b').into(),
+                KillLine => Ctrl('k').into(),
+                KillWord => Alt('d').into(),
+                Yank => Ctrl('y').into(),
+                YankPop => Alt('y').into(),
+                TransposeChars => Ctrl('t').into(),
+                TransposeWords => Alt('t').into(),
+                Undo => Ctrl('z').into(),
+                UpcaseWord => Alt('u').into(),
+                DowncaseWord => Alt('l').into(),
+                CapitalizeWord => Alt('c').into(),
+                // Followed by C-e, like in bash
+                // Takes two keys, see below
+                EditInEditor => continue,
+                HistorySearch => Ctrl('r').into(),
             };
 
             minibuffer.insert(key, action.as_default());
         }
 
         minibuffer.insert(Esc, Cancel);
+        minibuffer.insert_sequence(vec![Ctrl('x').into(), Ctrl('e').into()], EditInEditor);
         minibuffer.insert(AnyKey::AnyChar, InsertChar('E'));
         minibuffer.insert(Ctrl('d'), DeleteChar);
         minibuffer.insert(Left, CursorLeft);
@@ -1023,6 +1592,331 @@ impl BindingSection for QuickActionAction {
     }
 }
 
+impl Default for Bindings<BasketAction> {
+    fn default() -> Self {
+        use BasketAction::*;
+        use Key::*;
+
+        let mut basket = Bindings::new();
+
+        for action in BasketAction::iter() {
+            let key = match action {
+                Close => Char('Y'),
+                Goto => Char('\n'),
+                Remove => Char('d'),
+                Clear => Char('D'),
+                QuickActions => Char('a'),
+                ExecCmd => Char('!'),
+            };
+
+            basket.insert(key, action.as_default());
+        }
+
+        basket.insert(Esc, Close);
+
+        basket
+    }
+}
+
+impl BindingSection for BasketAction {
+    fn section() -> &'static str {
+        "basket"
+    }
+}
+
+// The key pressed after TagWith, SelectTagged or FilterTagged
+#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+pub enum TagAction {
+    Label(char),
+    Cancel,
+}
+
+impl Default for Bindings<TagAction> {
+    fn default() -> Self {
+        use Key::*;
+        use TagAction::*;
+
+        let mut tag = Bindings::new();
+
+        for action in TagAction::iter() {
+            let key = match action {
+                Label(_) => AnyKey::AnyChar,
+                Cancel => Esc.into(),
+            };
+
+            tag.insert(key, action.as_default());
+        }
+
+        tag.insert(Ctrl('c'), Cancel);
+
+        tag
+    }
+}
+
+impl BindingSection for TagAction {
+    fn section() -> &'static str {
+        "tags"
+    }
+
+    fn insert_config_param(self, param: CharOrNum) -> Self {
+        use TagAction::*;
+
+        match self {
+            Label(_) => Label(param.char_or(crate::files::DEFAULT_TAG)),
+            _ => self,
+        }
+    }
+
+    fn insert_key_param(self, key: Key) -> Self {
+        use TagAction::*;
+
+        match (self, key) {
+            (Label(_), Key::Char(ch)) => Label(ch),
+            _ => self,
+        }
+    }
+}
+
+#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+pub enum TaggedAction {
+    Close,
+    Goto,
+    Untag,
+    UntagMissing,
+    QuickActions,
+    ExecCmd,
+}
+
+impl Default for Bindings<TaggedAction> {
+    fn default() -> Self {
+        use Key::*;
+        use TaggedAction::*;
+
+        let mut tagged = Bindings::new();
+
+        for action in TaggedAction::iter() {
+            let key = match action {
+                Close => Char('T'),
+                Goto => Char('\n'),
+                Untag => Char('d'),
+                UntagMissing => Char('D'),
+                QuickActions => Char('a'),
+                ExecCmd => Char('!'),
+            };
+
+            tagged.insert(key, action.as_default());
+        }
+
+        tagged.insert(Esc, Close);
+
+        tagged
+    }
+}
+
+impl BindingSection for TaggedAction {
+    fn section() -> &'static str {
+        "tagged"
+    }
+}
+
+#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+pub enum JumpListAction {
+    Close,
+    Goto,
+}
+
+impl Default for Bindings<JumpListAction> {
+    fn default() -> Self {
+        use JumpListAction::*;
+        use Key::*;
+
+        let mut jumplist = Bindings::new();
+
+        for action in JumpListAction::iter() {
+            let key = match action {
+                Close => Ctrl('o'),
+                Goto => Char('\n'),
+            };
+
+            jumplist.insert(key, action.as_default());
+        }
+
+        jumplist.insert(Esc, Close);
+
+        jumplist
+    }
+}
+
+impl BindingSection for JumpListAction {
+    fn section() -> &'static str {
+        "jumplist"
+    }
+}
+
+#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+pub enum CompletionAction {
+    Next,
+    Prev,
+    Accept,
+    Close,
+}
+
+impl Default for Bindings<CompletionAction> {
+    fn default() -> Self {
+        use CompletionAction::*;
+        use Key::*;
+
+        let mut completion = Bindings::new();
+
+        for action in CompletionAction::iter() {
+            let key = match action {
+                Next => Char('\t'),
+                Prev => BackTab,
+                Accept => Char('\n'),
+                Close => Esc,
+            };
+
+            completion.insert(key, action.as_default());
+        }
+
+        completion.insert(Ctrl('n'), Next);
+        completion.insert(Down, Next);
+        completion.insert(Ctrl('p'), Prev);
+        completion.insert(Up, Prev);
+        completion.insert(Ctrl('g'), Close);
+
+        completion
+    }
+}
+
+impl BindingSection for CompletionAction {
+    fn section() -> &'static str {
+        "completion"
+    }
+}
+
+#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+pub enum HistorySearchAction {
+    InsertChar(char),
+    BackwardDeleteChar,
+    Older,
+    Newer,
+    Accept,
+    Edit,
+    Cancel,
+}
+
+impl Default for Bindings<HistorySearchAction> {
+    fn default() -> Self {
+        use HistorySearchAction::*;
+        use Key::*;
+
+        let mut historysearch = Bindings::new();
+
+        for action in HistorySearchAction::iter() {
+            let key = match action {
+                InsertChar(_) => AnyKey::AnyChar,
+                BackwardDeleteChar => Backspace.into(),
+                Older => Ctrl('r').into(),
+                Newer => Ctrl('s').into(),
+                Accept => Char('\n').into(),
+                Edit => Esc.into(),
+                Cancel => Ctrl('g').into(),
+            };
+
+            historysearch.insert(key, action.as_default());
+        }
+
+        historysearch.insert(AnyKey::AnyChar, InsertChar('E'));
+        historysearch.insert(Left, Edit);
+        historysearch.insert(Right, Edit);
+        historysearch.insert(Ctrl('c'), Cancel);
+
+        historysearch
+    }
+}
+
+#[derive(EnumString, EnumIter, Copy, Clone, Display, Debug)]
+pub enum PaletteAction {
+    InsertChar(char),
+    BackwardDeleteChar,
+    Prev,
+    Next,
+    Run,
+    Close,
+}
+
+impl Default for Bindings<PaletteAction> {
+    fn default() -> Self {
+        use PaletteAction::*;
+        use Key::*;
+
+        let mut palette = Bindings::new();
+
+        for action in PaletteAction::iter() {
+            let key = match action {
+                InsertChar(_) => AnyKey::AnyChar,
+                BackwardDeleteChar => Backspace.into(),
+                Prev => Ctrl('p').into(),
+                Next => Ctrl('n').into(),
+                Run => Char('\n').into(),
+                Close => Esc.into(),
+            };
+
+            palette.insert(key, action.as_default());
+        }
+
+        palette.insert(AnyKey::AnyChar, InsertChar('E'));
+        palette.insert(Up, Prev);
+        palette.insert(Down, Next);
+        palette.insert(Ctrl('g'), Close);
+        palette.insert(Alt('x'), Close);
+        palette.insert(Char('?'), Close);
+
+        palette
+    }
+}
+
+impl BindingSection for PaletteAction {
+    fn section() -> &'static str {
+        "palette"
+    }
+
+    fn insert_config_param(self, param: CharOrNum) -> Self {
+        match self {
+            PaletteAction::InsertChar(_) => PaletteAction::InsertChar(param.char_or('E')),
+            _ => self,
+        }
+    }
+
+    fn insert_key_param(self, key: Key) -> Self {
+        match (self, key) {
+            (PaletteAction::InsertChar(_), Key::Char(ch)) => PaletteAction::InsertChar(ch),
+            _ => self,
+        }
+    }
+}
+
+impl BindingSection for HistorySearchAction {
+    fn section() -> &'static str {
+        "historysearch"
+    }
+
+    fn insert_config_param(self, param: CharOrNum) -> Self {
+        match self {
+            HistorySearchAction::InsertChar(_) => HistorySearchAction::InsertChar(param.char_or('E')),
+            _ => self,
+        }
+    }
+
+    fn insert_key_param(self, key: Key) -> Self {
+        match (self, key) {
+            (HistorySearchAction::InsertChar(_), Key::Char(ch)) => HistorySearchAction::InsertChar(ch),
+            _ => self,
+        }
+    }
+}
+
 #[test]
 fn test_keyparse() {
     let keys = ["C-a", "A-_", "Delete", "a", "F9", "C-_"];
@@ -1033,3 +1927,81 @@ fn test_keyparse() {
         dbg!(parsed).ok();
     }
 }
+
+#[test]
+fn test_sequences() {
+    use termion::event::Key::*;
+
+    let keys = |keys: &str| parse_keys(keys).unwrap();
+    assert_eq!(keys("gg"), vec![AnyKey::Key(Char('g')); 2]);
+    assert_eq!(keys("C-x C-e"), vec![AnyKey::Key(Ctrl('x')), AnyKey::Key(Ctrl('e'))]);
+    assert_eq!(keys("Space"), vec![AnyKey::Key(Char(' '))]);
+    assert!(parse_keys("Pageup").is_err());
+    assert!(parse_keys("C-x-e").is_err());
+
+    let mut bindings = Bindings::<BookmarkAction>::new();
+    bindings.insert_sequence(keys("g _"), BookmarkAction::Goto('x'));
+    assert!(bindings.is_prefix(&[Char('g')]));
+    assert!(!bindings.is_prefix(&[Char('g'), Char('a')]));
+    assert!(matches!(bindings.get_sequence(&[Char('g'), Char('a')]),
+                     Some(BookmarkAction::Goto('a'))));
+}
+
+#[test]
+fn test_user_command() {
+    let command = "(prompt, wait) zip -r $i.zip $s".parse::<UserCommand>().unwrap();
+    assert_eq!(command.cmd, "zip -r $i.zip $s");
+    assert!(command.prompt && command.wait && command.fg && !command.refresh);
+
+    // Not options, just a subshell
+    let command = "(cd build; make)".parse::<UserCommand>().unwrap();
+    assert_eq!(command.cmd, "(cd build; make)");
+    assert!(!command.fg);
+
+    assert!("(fg)".parse::<UserCommand>().is_err());
+    assert!("(refresh) make".parse::<UserCommand>().is_err());
+    assert!("(refresh, wait) make".parse::<UserCommand>().unwrap().refresh);
+}
+
+#[test]
+fn test_movement_count() {
+    assert!(matches!(Movement::Down(10).with_count(5), Some(Movement::Down(5))));
+    assert!(matches!(Movement::Up(1).with_count(3), Some(Movement::Up(3))));
+    assert!(Movement::PageDown.with_count(2).is_none());
+}
+
+#[test]
+fn test_pending_keys() {
+    let (a, b) = (Key::Char('a'), Key::Char('b'));
+    let mut pending = PendingKeys::default();
+
+    let first = pending.next_event();
+    assert_eq!(pending.push(a, vec![]), first);
+    assert!(pending.timed_out(first));
+
+    // Only the very next key goes on with the sequence
+    let second = pending.next_event();
+    assert_eq!(pending.keys(), &[a]);
+    pending.push(b, vec![]);
+    assert!(!pending.timed_out(first));
+    assert!(pending.timed_out(second));
+
+    // A key after it that no widget took
+    pending.next_event();
+    assert_eq!(pending.keys(), &[a, b]);
+    assert!(pending.went_nowhere());
+    assert!(!pending.timed_out(second));
+
+    let keys = pending.take_keys();
+    assert_eq!(keys, vec![a, b]);
+    assert!(!pending.went_nowhere());
+
+    pending.finish(&keys[..1]);
+    assert!(pending.is_finishing());
+    assert_eq!(pending.keys(), &[a]);
+    assert!(!pending.went_nowhere());
+
+    pending.clear();
+    assert!(pending.keys().is_empty());
+    assert!(!pending.is_finishing());
+}
@@ -7,6 +7,7 @@ use unicode_width::UnicodeWidthStr;
 
 use async_value::Stale;
 
+use crate::config::LineNumbers;
 use crate::dirty::Dirtyable;
 use crate::fail::{ErrorLog, WError, WResult};
 use crate::files::{File, Files};
@@ -76,6 +77,45 @@ impl Acting for ListView<Files> {
         Ok(())
     }
 
+    fn movement_count(&mut self, movement: &Movement, count: usize) -> WResult<()> {
+        use Movement::*;
+
+        match (movement, movement.with_count(count)) {
+            // Like "5G" in vim, jump to that line
+            (Top, _) | (Bottom, _) => {
+                let pos = self.get_selection();
+                let line = count.saturating_sub(1).min(self.len().saturating_sub(1));
+                self.set_selection(line);
+                if pos != self.get_selection() {
+                    self.update_selected_file(pos);
+                }
+                Ok(())
+            }
+            (_, Some(movement)) => self.movement(&movement),
+            (_, None) => {
+                for _ in 0..count {
+                    self.movement(movement)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn do_action_count(&mut self, action: &Self::Action, count: usize) -> WResult<()> {
+        use FileListAction::*;
+
+        match action {
+            _ if self.visual_anchor.is_some() => self.do_action(action),
+            Select | ToggleTag | SearchNext | SearchPrev | ToNextMtime | ToPrevMtime => {
+                for _ in 0..count {
+                    self.do_action(action)?;
+                }
+                Ok(())
+            }
+            _ => self.do_action(action),
+        }
+    }
+
     fn do_action(&mut self, action: &Self::Action) -> WResult<()> {
         use FileListAction::*;
 
@@ -84,6 +124,10 @@ impl Acting for ListView<Files> {
             SearchNext => self.search_next()?,
             SearchPrev => self.search_prev()?,
             Filter => self.filter()?,
+            // In visual mode these work on the whole range
+            Select if self.visual_anchor.is_some() => self.visual_select(true, false),
+            InvertSelection if self.visual_anchor.is_some() => self.visual_select(false, true),
+            ClearSelection if self.visual_anchor.is_some() => self.visual_select(false, false),
             Select => self.multi_select_file(),
             InvertSelection => self.invert_selection(),
             ClearSelection => self.clear_selections(),
@@ -95,6 +139,7 @@ impl Acting for ListView<Files> {
             ToNextMtime => self.select_next_mtime(),
             ToPrevMtime => self.select_prev_mtime(),
             ToggleDirsFirst => self.toggle_dirs_first(),
+            VisualMode => self.toggle_visual_mode(),
         }
 
         Ok(())
@@ -164,6 +209,7 @@ where
     pub core: WidgetCore,
     seeking: bool,
     searching: Option<String>,
+    visual_anchor: Option<usize>,
 }
 
 impl<T> ListView<T>
@@ -180,6 +226,7 @@ where
             core: core.clone(),
             seeking: false,
             searching: None,
+            visual_anchor: None,
         };
         view.on_new().log();
         view
@@ -471,6 +518,15 @@ impl ListView<Files> {
             .log();
     }
 
+    pub fn set_sort(&mut self, sort: crate::files::SortBy, reverse: bool) {
+        let file = self.clone_selected_file();
+        self.content.sort = sort;
+        self.content.reverse = reverse;
+        self.content.sort();
+        self.select_file(&file);
+        self.refresh().log();
+    }
+
     fn reverse_sort(&mut self) {
         let file = self.clone_selected_file();
         self.content.reverse_sort();
@@ -596,6 +652,22 @@ impl ListView<Files> {
         self.refresh().log();
     }
 
+    // Adds to the selection, returns how many files matched
+    pub fn select_glob(&mut self, pattern: &str) -> usize {
+        let mut count = 0;
+
+        for file in self.content.iter_files_mut() {
+            if crate::shell::glob_match(pattern, &file.name) {
+                file.selected = true;
+                count += 1;
+            }
+        }
+
+        self.content.set_dirty();
+        self.refresh().log();
+        count
+    }
+
     pub fn clear_selections(&mut self) {
         for file in self.content.iter_files_mut() {
             file.selected = false;
@@ -604,8 +676,65 @@ impl ListView<Files> {
         self.refresh().log();
     }
 
+    fn toggle_visual_mode(&mut self) {
+        match self.visual_anchor.take() {
+            Some(_) => self.core.show_status("Visual mode off").log(),
+            None => {
+                self.visual_anchor = Some(self.get_selection());
+                self.core
+                    .show_status("Visual mode: move to mark a range, then select/invert/clear it")
+                    .log();
+            }
+        }
+        self.core.set_dirty();
+    }
+
+    pub fn visual_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.visual_anchor?;
+        let pos = self.get_selection();
+        Some((anchor.min(pos), anchor.max(pos)))
+    }
+
+    // Adds the range between the anchor and the cursor to the
+    // selection, removes it or toggles it, then leaves visual mode
+    fn visual_select(&mut self, select: bool, toggle: bool) {
+        let (start, end) = match self.visual_range() {
+            Some(range) => range,
+            None => return,
+        };
+
+        for file in self.content.iter_files_mut().skip(start).take(end + 1 - start) {
+            match toggle {
+                true => file.toggle_selection(),
+                false => file.selected = select,
+            }
+        }
+
+        self.visual_anchor = None;
+
+        if self.content.filter_selected && self.content.len() == 0 {
+            self.content.toggle_filter_selected();
+            self.core.show_status("Disabled selection filter!").log();
+        }
+
+        let count = end + 1 - start;
+        let status = match (select, toggle) {
+            (_, true) => format!("Toggled {} files", count),
+            (true, _) => format!("Selected {} files", count),
+            (false, _) => format!("Unselected {} files", count),
+        };
+        self.core.show_status(&status).log();
+
+        self.content.set_dirty();
+        self.refresh().log();
+    }
+
     fn toggle_tag(&mut self) -> WResult<()> {
-        self.selected_file_mut().toggle_tag()?;
+        self.toggle_tag_label(crate::files::DEFAULT_TAG)
+    }
+
+    pub fn toggle_tag_label(&mut self, label: char) -> WResult<()> {
+        self.selected_file_mut().toggle_tag_label(label)?;
 
         let oldpos = self.get_selection();
         self.move_down();
@@ -618,6 +747,42 @@ impl ListView<Files> {
         Ok(())
     }
 
+    pub fn select_tagged(&mut self, label: char) {
+        let mut count = 0;
+
+        for file in self.content.iter_files_mut().filter(|file| file.has_tag(label)) {
+            file.selected = true;
+            count += 1;
+        }
+
+        self.core
+            .show_status(&format!("Selected {} files tagged {}", count, label))
+            .log();
+
+        self.content.set_dirty();
+        self.refresh().log();
+    }
+
+    // Filtering by the same label again turns it off
+    pub fn filter_tagged(&mut self, label: char) {
+        let filter_tag = match self.content.filter_tag {
+            Some(tag) if tag == label => None,
+            _ => Some(label),
+        };
+
+        let selected_file = self.clone_selected_file();
+        self.content.set_filter_tag(filter_tag);
+        self.refresh().log();
+        self.select_file(&selected_file);
+        self.core.clear().log();
+
+        let status = match filter_tag {
+            Some(label) => format!("Showing files tagged {}", label),
+            None => String::from("Showing all files"),
+        };
+        self.core.show_status(&status).log();
+    }
+
     fn search_file(&mut self) -> WResult<()> {
         let selected_file = self.clone_selected_file();
 
@@ -814,17 +979,22 @@ impl ListView<Files> {
         use std::fmt::Write;
 
         let xsize = self.get_coordinates().unwrap().xsize();
+        let xsize = xsize.saturating_sub(self.line_number_width() as u16);
         let config = self.core.config();
         let icons = config.icons;
         let icons_space = config.icons_space;
 
+        // The selected directory is the one shown in the next column
+        let open_dir = self.selected_file().path.clone();
+
         move |file| -> String {
             let mut line = String::with_capacity(500);
 
-            let (icon, icon_space) = match (icons, icons_space) {
-                (true, true) => (file.icon(), " "),
-                (true, false) => (file.icon(), ""),
-                _ => ("", ""),
+            let open = file.path == open_dir;
+            let ((icon, icon_color), icon_space) = match (icons, icons_space) {
+                (true, true) => (file.icon_with_color(open), " "),
+                (true, false) => (file.icon_with_color(open), ""),
+                _ => (("", None), ""),
             };
 
             let name = &file.name;
@@ -839,12 +1009,13 @@ impl ListView<Files> {
                 Err(_) => (String::from("ERR"), ""),
             };
 
-            let (tag, tag_len) = match file.is_tagged() {
-                Ok(true) => (Some(term::color_red() + "*"), 1),
-                _ => (None, 0),
-            };
-
-            let tag = tag.as_ref().map(|t| t.as_str()).unwrap_or("");
+            // Every label in its own color
+            let labels = file.get_tags().unwrap_or_default();
+            let tag = labels
+                .iter()
+                .map(|label| format!("{}{}", config.tag_color(*label), label))
+                .collect::<String>();
+            let tag_len = labels.len() as u16;
 
             let selection_color = crate::term::color_yellow();
             let (selection_gap, selection_color) = match file.is_selected() {
@@ -871,18 +1042,28 @@ impl ListView<Files> {
             let sized_string = term::sized_string(&name, xsize);
 
             let size = size.to_string();
-            let size_pos =
-                xsize - (size.len() as u16 + unit.len() as u16 + link_indicator_len as u16);
+            let size_pos = xsize
+                .saturating_sub(size.len() as u16 + unit.len() as u16 + link_indicator_len as u16);
 
+            // Narrow columns, like with line numbers, leave no room at all
             let padding = sized_string.len() - sized_string.width_cjk();
-            let padding = xsize - padding as u16;
-            let padding = padding - tag_len;
-            let padding = padding - icon.width() as u16;
-            let padding = padding - icon_space.len() as u16;
-            let padding = padding - 1;
+            let padding = xsize.saturating_sub(padding as u16);
+            let padding = padding.saturating_sub(tag_len);
+            let padding = padding.saturating_sub(icon.width() as u16);
+            let padding = padding.saturating_sub(icon_space.len() as u16);
+            let padding = padding.saturating_sub(1);
 
             write!(&mut line, "{}", termion::cursor::Save).unwrap();
 
+            // Switch to the icon color and back again for the name
+            let file_color = file.get_color().unwrap_or_else(term::normal_color);
+            let icon = match icon_color {
+                Some(icon_color) => {
+                    format!("{}{}{}{}", icon_color, icon, file_color, selection_color)
+                }
+                None => icon.to_string(),
+            };
+
             match file.get_color() {
                 Some(color) => write!(
                     &mut line,
@@ -934,14 +1115,50 @@ impl ListView<Files> {
         let ysize = self.get_coordinates().unwrap().ysize_u();
         let files_above_selection = self.get_selection() - self.offset;
         let selected_file = self.selected_file();
+        let line_numbers = self.core.config().line_numbers;
+        let width = self.line_number_width();
+        let visual_range = self.visual_range();
 
         self.content
             .iter_files_from(selected_file, files_above_selection)
             .take(ysize + 1)
-            .map(|file| render_fn(file))
+            .enumerate()
+            .map(|(i, file)| {
+                let line = i + self.offset;
+
+                let highlight = match visual_range {
+                    Some((start, end)) if line >= start && line <= end => term::visual_bg(),
+                    _ => String::new(),
+                };
+
+                let number = match line_numbers {
+                    LineNumbers::Absolute => line + 1,
+                    LineNumbers::Relative => {
+                        (line as isize - self.selection as isize).unsigned_abs()
+                    }
+                    LineNumbers::Off => return highlight + &render_fn(file),
+                };
+
+                format!(
+                    "{}{}{:>width$} {}",
+                    highlight,
+                    term::color_light_black(),
+                    number,
+                    render_fn(file),
+                    width = width - 1
+                )
+            })
             .collect()
     }
 
+    // Width of the line number gutter, including the space after it
+    fn line_number_width(&self) -> usize {
+        match self.core.config().line_numbers {
+            LineNumbers::Absolute | LineNumbers::Relative => self.len().to_string().len() + 1,
+            LineNumbers::Off => 0,
+        }
+    }
+
     fn refresh_files(&mut self) -> WResult<()> {
         let file = self.clone_selected_file();
 
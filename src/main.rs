@@ -37,8 +37,12 @@ use clap::{App, Arg};
 
 use std::panic;
 
+mod basket;
 mod bookmarks;
+mod cmdline;
+mod completion;
 mod config;
+mod config_check;
 mod config_installer;
 mod coordinates;
 mod dirty;
@@ -46,20 +50,27 @@ mod fail;
 mod file_browser;
 mod files;
 mod foldview;
+mod frecency;
 mod fscache;
 mod hbox;
 mod icon;
 mod imgview;
+mod jumplist;
 mod keybind;
 mod listview;
 mod mediaview;
 mod miller_columns;
 mod minibuffer;
+mod palette;
+mod pathlist;
 mod paths;
 mod preview;
 mod proclist;
 mod quick_actions;
+mod session;
+mod shell;
 mod stats;
+mod tagged;
 mod tabview;
 mod term;
 mod textview;
@@ -90,6 +101,12 @@ fn die_gracefully(core: &WidgetCore) {
 
 fn main() -> WResult<()> {
     let args = parse_args();
+    let session = args.value_of("session").map(String::from);
+
+    // Needs no terminal, so it can run in scripts
+    if args.is_present("check-config") {
+        std::process::exit(crate::config_check::run());
+    }
 
     // do this early so it might be ready when needed
     crate::files::load_tags().ok();
@@ -101,7 +118,7 @@ fn main() -> WResult<()> {
     // Resets terminal when hunter crashes :(
     die_gracefully(&core);
 
-    match run(core.clone()) {
+    match run(core.clone(), session) {
         Ok(_) | Err(WError::Quit) => reset_screen(&mut core),
         Err(err) => {
             reset_screen(&mut core)?;
@@ -111,7 +128,7 @@ fn main() -> WResult<()> {
     }
 }
 
-fn run(mut core: WidgetCore) -> WResult<()> {
+fn run(mut core: WidgetCore, session: Option<String>) -> WResult<()> {
     core.screen.clear()?;
 
     let core2 = core.clone();
@@ -121,11 +138,32 @@ fn run(mut core: WidgetCore) -> WResult<()> {
         crate::config_installer::ensure_config(core2).log();
     });
 
-    let filebrowser = FileBrowser::new(&core, None)?;
     let mut tabview = TabView::new(&core);
-    tabview.push_widget(filebrowser)?;
 
-    tabview.handle_input()?;
+    if let Some(name) = &session {
+        crate::session::set_name(name).log();
+    }
+
+    let session = session.and_then(|name| {
+        crate::session::Session::load(&name).log_and().ok()
+    });
+
+    match session {
+        Some(session) => tabview.restore_session(session)?,
+        None => {
+            let filebrowser = FileBrowser::new(&core, None)?;
+            tabview.push_widget(filebrowser)?;
+        }
+    }
+
+    let result = tabview.handle_input();
+
+    // Save the tabs, so they can be restored with --session
+    if let Ok(_) | Err(WError::Quit) = result {
+        tabview.save_session_as(&crate::session::name()).log();
+    }
+
+    result?;
 
     // core.screen.cursor_show()?;
     // core.screen.flush()?;
@@ -177,6 +215,23 @@ fn parse_args() -> clap::ArgMatches<'static> {
                 .long("mime")
                 .help("Print MIME type of file")
                 .takes_value(false))
+        .arg(
+            Arg::with_name("session")
+                .short("s")
+                .long("session")
+                .help("Restore tabs from session <name>, saved again on quit")
+                .takes_value(true))
+        .arg(
+            Arg::with_name("import-frecency")
+                .long("import-frecency")
+                .help("Import visited directories from zoxide, z or autojump")
+                .possible_values(&["zoxide", "z", "autojump"])
+                .takes_value(true))
+        .arg(
+            Arg::with_name("check-config")
+                .long("check-config")
+                .help("Check config, keys, actions and previewers, then quit")
+                .takes_value(false))
         .arg(
             Arg::with_name("path")
                 .index(1)
@@ -194,6 +249,20 @@ fn process_args(args: clap::ArgMatches, core: WidgetCore) {
         std::process::exit(1)
     }
 
+    // Import the other jumper's database and quit
+    if let Some(source) = args.value_of("import-frecency") {
+        match crate::frecency::import(source) {
+            Ok(count) => {
+                println!("Imported {} directories from {}", count, source);
+                std::process::exit(0)
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1)
+            }
+        }
+    }
+
     if args.is_present("update") {
         crate::config_installer::update_config(core, true).log();
     }
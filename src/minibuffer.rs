@@ -1,18 +1,62 @@
 use termion::event::Key;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+use crate::completion::Completion;
 use crate::coordinates::Coordinates;
 use crate::fail::{ErrorLog, WError, WResult};
 use crate::term::ScreenExt;
 use crate::widget::{Widget, WidgetCore};
 
-type HMap = HashMap<String, Vec<String>>;
+// Oldest kills are dropped after that
+const MAX_KILLS: usize = 20;
+
+#[derive(Clone, Debug)]
+struct Entry {
+    // The prompt it was entered at, like "exec"
+    htype: String,
+    line: String,
+    dir: Option<String>,
+}
+
+impl Entry {
+    // Older files only have "htype:line", now it's "htype\tdir\tline"
+    fn parse(line: &str) -> Option<Entry> {
+        let sep = line.find([':', '\t'])?;
+        let (htype, rest) = (line[..sep].to_string(), &line[sep + 1..]);
+
+        match &line[sep..=sep] {
+            ":" => Some(Entry { htype, line: rest.to_string(), dir: None }),
+            _ => {
+                let mut parts = rest.splitn(2, '\t');
+                let dir = parts.next()?.to_string();
+                let line = parts.next()?.to_string();
+                Some(Entry { htype, line, dir: Some(dir) })
+            }
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match &self.dir {
+            Some(dir) => format!("{}\t{}\t{}\n", self.htype, dir, self.line),
+            None => format!("{}:{}\n", self.htype, self.line),
+        }
+    }
+}
+
+fn current_dir() -> Option<String> {
+    std::env::current_dir()
+        .ok()
+        .map(|dir| dir.to_string_lossy().to_string())
+}
 
 #[derive(Debug)]
 struct History {
-    history: HMap,
+    // Oldest first
+    entries: Vec<Entry>,
     position: Option<usize>,
     loaded: bool,
 }
@@ -20,83 +64,129 @@ struct History {
 impl History {
     fn new() -> History {
         History {
-            history: HashMap::new(),
+            entries: vec![],
             position: None,
             loaded: false,
         }
     }
 
+    fn read() -> WResult<Vec<Entry>> {
+        let hpath = crate::paths::history_path()?;
+        let hf_content = match std::fs::read_to_string(hpath) {
+            Ok(content) => content,
+            // Nothing entered yet
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(hf_content.lines().filter_map(Entry::parse).collect())
+    }
+
     fn load(&mut self) -> WResult<()> {
         if self.loaded {
             return Ok(());
         }
 
-        let hpath = crate::paths::history_path()?;
-        let hf_content = std::fs::read_to_string(hpath)?;
-
-        let history = hf_content
-            .lines()
-            .fold(HashMap::new(), |mut hm: HMap, line| {
-                let parts = line.splitn(2, ":").collect::<Vec<&str>>();
-                if parts.len() == 2 {
-                    let (htype, hline) = (parts[0].to_string(), parts[1].to_string());
-
-                    match hm.get_mut(&htype) {
-                        Some(hvec) => hvec.push(hline),
-                        None => {
-                            let hvec = vec![hline];
-                            hm.insert(htype, hvec);
-                        }
-                    };
-                }
-                hm
-            });
-
-        self.history = history;
+        self.entries = History::read()?;
         self.loaded = true;
 
         Ok(())
     }
 
+    // Written to a temporary file first, so other instances never see
+    // half of it
     fn save(&self) -> WResult<()> {
         let hpath = crate::paths::history_path()?;
-
         let history = self
-            .history
+            .entries
             .iter()
-            .map(|(htype, hlines)| {
-                hlines
-                    .iter()
-                    .map(|hline| format!("{}:{}\n", htype, hline))
-                    .collect::<String>()
-            })
+            .map(Entry::to_line)
             .collect::<String>();
 
-        std::fs::write(hpath, history)?;
-        Ok(())
+        crate::paths::write_atomic(&hpath, history.as_bytes())
     }
 
     fn reset(&mut self) {
         self.position = None;
     }
 
-    fn add(&mut self, htype: &str, input: &str) {
-        self.load().ok();
-        let history = match self.history.get_mut(htype) {
-            Some(history) => history,
-            None => {
-                let hvec = Vec::new();
-                self.history.insert(htype.to_string(), hvec);
-                self.history.get_mut(htype).unwrap()
+    // Only the last use of a line is kept, and at most size lines for
+    // each prompt
+    fn tidy(&mut self, size: usize) {
+        let mut seen = HashSet::new();
+        let mut counts = HashMap::new();
+
+        self.entries.reverse();
+        self.entries.retain(|entry| {
+            let count = counts.entry(entry.htype.clone()).or_insert(0);
+            let keep = *count < size && seen.insert((entry.htype.clone(), entry.line.clone()));
+            if keep {
+                *count += 1;
             }
-        };
-        history.push(input.to_string());
-        self.save().log();
+            keep
+        });
+        self.entries.reverse();
+    }
+
+    fn add(&mut self, htype: &str, input: &str, size: usize) -> WResult<()> {
+        // Pick up what other instances added in the meantime. Nothing is
+        // saved when that fails, or the history would be lost.
+        self.entries = History::read()?;
+        self.loaded = true;
+
+        self.entries.push(Entry {
+            htype: htype.to_string(),
+            line: input.to_string(),
+            dir: current_dir(),
+        });
+        self.tidy(size);
+        self.save()
+    }
+
+    // Lines of one prompt, oldest first. Ranked by directory the ones
+    // from the current directory are moved to the end.
+    fn lines(&mut self, htype: &str, by_dir: bool) -> WResult<Vec<String>> {
+        self.load()?;
+
+        let cwd = current_dir();
+        let mut entries = self
+            .entries
+            .iter()
+            .filter(|entry| entry.htype == htype)
+            .collect::<Vec<_>>();
+
+        if by_dir {
+            entries.sort_by_key(|entry| entry.dir == cwd);
+        }
+
+        Ok(entries.into_iter().map(|entry| entry.line.clone()).collect())
     }
 
-    fn get_prev(&mut self, htype: &str) -> WResult<String> {
+    // Lines containing pattern, best match first
+    fn search(&mut self, pattern: &str, htype: &str, by_dir: bool) -> WResult<Vec<String>> {
         self.load()?;
-        let history = self.history.get(htype).ok_or(WError::NoneError)?;
+
+        let cwd = current_dir();
+        let mut matches = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.line.contains(pattern))
+            .collect::<Vec<_>>();
+
+        // Stable, so newer entries stay in front
+        matches.sort_by_key(|entry| (by_dir && entry.dir != cwd, entry.htype != htype));
+
+        let mut seen = HashSet::new();
+        Ok(matches
+            .into_iter()
+            .filter(|entry| seen.insert(&entry.line))
+            .map(|entry| entry.line.clone())
+            .collect())
+    }
+
+    fn get_prev(&mut self, htype: &str, by_dir: bool) -> WResult<String> {
+        let history = self.lines(htype, by_dir)?;
         let mut position = self.position;
         let hist_len = history.len();
 
@@ -118,9 +208,8 @@ impl History {
         }
     }
 
-    fn get_next(&mut self, htype: &str) -> WResult<String> {
-        self.load()?;
-        let history = self.history.get(htype).ok_or(WError::NoneError)?;
+    fn get_next(&mut self, htype: &str, by_dir: bool) -> WResult<String> {
+        let history = self.lines(htype, by_dir)?;
         let mut position = self.position;
         let hist_len = history.len();
 
@@ -143,6 +232,24 @@ impl History {
     }
 }
 
+// State of an incremental search started with HistorySearch
+#[derive(Debug)]
+struct HistorySearch {
+    pattern: String,
+    // Best match first
+    matches: Vec<String>,
+    selection: usize,
+    // Restored when the search is cancelled
+    input: String,
+    position: usize,
+}
+
+impl HistorySearch {
+    fn selected(&self) -> Option<&String> {
+        self.matches.get(self.selection)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum MiniBufferEvent {
     Done(String),
@@ -160,9 +267,16 @@ pub struct MiniBuffer {
     input: String,
     position: usize,
     history: History,
-    completions: Vec<OsString>,
-    last_completion: Option<String>,
+    // Shown above the minibuffer while completing
+    completion: Option<Completion>,
+    search: Option<HistorySearch>,
     continuous: bool,
+    // Shared by all queries, like in readline
+    kill_ring: Vec<String>,
+    // Start of the last yank and its place in the kill ring, for YankPop
+    yanked: Option<(usize, usize)>,
+    undo: Vec<(String, usize)>,
+    last_action: Option<MiniBufferAction>,
 }
 
 impl MiniBuffer {
@@ -178,9 +292,13 @@ impl MiniBuffer {
             input: String::new(),
             position: 0,
             history: History::new(),
-            completions: vec![],
-            last_completion: None,
+            completion: None,
+            search: None,
             continuous: false,
+            kill_ring: vec![],
+            yanked: None,
+            undo: vec![],
+            last_action: None,
         }
     }
 
@@ -193,9 +311,17 @@ impl MiniBuffer {
             self.clear();
         }
 
+        if self.lists_candidates() {
+            self.completion = Completion::new(&self.query, &self.input);
+        }
+
         self.core.screen()?.cursor_hide().log();
 
-        match self.popup() {
+        let result = self.popup();
+        self.completion = None;
+        self.search = None;
+
+        match result {
             event @ Err(WError::MiniBufferEvent(_)) => event?,
             err @ Err(WError::RefreshParent) => err?,
             _ => {}
@@ -212,70 +338,95 @@ impl MiniBuffer {
     pub fn clear(&mut self) {
         self.input.clear();
         self.position = 0;
-        self.completions.clear();
-        self.last_completion = None;
+        self.completion = None;
+        self.search = None;
+        self.yanked = None;
+        self.undo.clear();
+        self.last_action = None;
     }
 
     pub fn complete(&mut self) -> WResult<()> {
-        if !self.input.ends_with(" ") {
-            if !self.completions.is_empty() {
-                self.cycle_completions()?;
-                return Ok(());
-            }
-
-            let part = self
-                .input
-                .rsplitn(2, " ")
-                .take(1)
-                .map(|s| s.to_string())
-                .collect::<String>();
-            let completions = find_files(&part);
-
-            if let Ok(mut completions) = completions {
-                let completion = completions.pop().ok_or(WError::NoneError)?;
-                let completion = completion.to_string_lossy();
-
-                self.input = self.input[..self.input.len() - part.len()].to_string();
-                self.input.push_str(&completion);
-                self.position += &completion.len() - part.len();
-
-                self.last_completion = Some(completion.to_string());
-                self.completions = completions;
-            } else {
-                let completions = find_bins(&part);
-
-                if let Ok(mut completions) = completions {
-                    let completion = completions.pop().ok_or(WError::NoneError)?;
-                    let completion = completion.to_string_lossy();
+        // Nothing to complete after a space, that's where the files go.
+        // Commands of the command prompt take arguments instead.
+        if self.query != "command" && !self.lists_candidates() &&
+            self.input[..self.position].ends_with(' ') {
+            self.input.insert_str(self.position, "$s");
+            self.position += 2;
+            return Ok(());
+        }
 
-                    self.input = self.input[..self.input.len() - part.len()].to_string();
-                    self.input.push_str(&completion);
-                    self.position += &completion.len() - part.len();
+        let completion = Completion::new(&self.query, &self.input[..self.position])
+            .ok_or(WError::NoCompletionsError)?;
 
-                    self.last_completion = Some(completion.to_string());
-                    self.completions = completions;
+        match completion.len() {
+            1 => self.replace_word(completion.start, &completion.replacement()),
+            _ => {
+                if let Some(prefix) = completion.common_prefix() {
+                    self.replace_word(completion.start, &prefix);
                 }
+                self.completion = Some(completion);
             }
-        } else {
-            self.input += "$s";
-            self.position += 2
         }
+
         Ok(())
     }
 
-    pub fn cycle_completions(&mut self) -> WResult<()> {
-        let last_comp = self.last_completion.as_ref().ok_or(WError::NoneError)?;
-        let last_len = last_comp.len();
+    // These prompts pick from a list, so it's shown right away
+    fn lists_candidates(&self) -> bool {
+        self.query == "jump"
+    }
 
-        self.input = self.input.trim_end_matches(last_comp).to_string();
-        self.position = self.position.saturating_sub(last_len);
+    fn replace_word(&mut self, start: usize, text: &str) {
+        self.input.replace_range(start..self.position, text);
+        self.position = start + text.len();
+    }
 
-        let next_comp = self.completions.pop().ok_or(WError::NoneError)?;
-        let next_comp = next_comp.to_string_lossy();
-        self.input.push_str(&next_comp);
-        self.position += next_comp.len();
-        self.last_completion = Some(next_comp.to_string());
-        Ok(())
+    fn close_completion(&mut self) {
+        if let Some(completion) = self.completion.take() {
+            let (xpos, ypos) = self.core.coordinates.u16position();
+            self.core.write_to_screen(&completion.clearlist(xpos, ypos)).log();
+        }
+    }
+
+    // Keys for the completion menu while it's shown
+    fn completion_key(&mut self, key: Key) -> Option<WResult<()>> {
+        use CompletionAction::*;
+
+        let action = self.core.config().keybinds.completion.get(key).cloned()?;
+        let completion = self.completion.as_mut()?;
+
+        match action {
+            Next => completion.next(),
+            Prev => completion.prev(),
+            Accept => {
+                let (start, text) = (completion.start, completion.replacement());
+                self.close_completion();
+                self.replace_word(start, &text);
+
+                if self.lists_candidates() {
+                    return Some(self.edit(&MiniBufferAction::Finish, false));
+                }
+            }
+            Close => self.close_completion(),
+        }
+
+        Some(Ok(()))
+    }
+
+    // The menu follows the input, it's gone once nothing matches
+    fn update_completion(&mut self) {
+        let label = match &self.completion {
+            Some(completion) => Some(completion.selected().label.clone()),
+            None if self.lists_candidates() => None,
+            None => return,
+        };
+
+        self.close_completion();
+        self.completion = Completion::new(&self.query, &self.input[..self.position]);
+
+        if let (Some(completion), Some(label)) = (self.completion.as_mut(), label) {
+            completion.select_label(&label);
+        }
     }
 
     pub fn history_up(&mut self) -> WResult<()> {
@@ -283,7 +434,8 @@ impl MiniBuffer {
             return Err(MiniBufferEvent::CyclePrev)?;
         }
 
-        if let Ok(historic) = self.history.get_prev(&self.query) {
+        let by_dir = self.core.config().history_dir_ranking;
+        if let Ok(historic) = self.history.get_prev(&self.query, by_dir) {
             self.position = historic.len();
             self.input = historic;
         }
@@ -295,13 +447,123 @@ impl MiniBuffer {
             return Err(MiniBufferEvent::CycleNext)?;
         }
 
-        if let Ok(historic) = self.history.get_next(&self.query) {
+        let by_dir = self.core.config().history_dir_ranking;
+        if let Ok(historic) = self.history.get_next(&self.query, by_dir) {
             self.position = historic.len();
             self.input = historic;
         }
         Ok(())
     }
 
+    pub fn history_search(&mut self) -> WResult<()> {
+        self.close_completion();
+        self.search = Some(HistorySearch {
+            pattern: String::new(),
+            matches: vec![],
+            selection: 0,
+            input: self.input.clone(),
+            position: self.position,
+        });
+        Ok(())
+    }
+
+    fn update_search(&mut self) -> WResult<()> {
+        let by_dir = self.core.config().history_dir_ranking;
+        let search = self.search.as_mut().ok_or(WError::NoneError)?;
+
+        search.selection = 0;
+        search.matches = match search.pattern.is_empty() {
+            true => vec![],
+            false => self.history
+                .search(&search.pattern, &self.query, by_dir)
+                .unwrap_or_default(),
+        };
+        Ok(())
+    }
+
+    // The match is put into the input, or what was there before
+    fn end_search(&mut self, keep: bool) {
+        let search = match self.search.take() {
+            Some(search) => search,
+            None => return,
+        };
+
+        match (keep, search.selected()) {
+            (true, Some(line)) => {
+                self.undo.push((self.input.clone(), self.position));
+                self.input = line.clone();
+                self.position = self.input.len();
+            }
+            (true, None) => {}
+            (false, _) => {
+                self.input = search.input;
+                self.position = search.position;
+            }
+        }
+    }
+
+    // Keys while searching, others end the search and work as usual
+    fn search_key(&mut self, key: Key) -> Option<WResult<()>> {
+        use HistorySearchAction::*;
+
+        self.search.as_ref()?;
+
+        let action = match self.core.config().keybinds.historysearch.get_any(key) {
+            Some(action) => action,
+            None => {
+                self.end_search(true);
+                return None;
+            }
+        };
+        let search = self.search.as_mut()?;
+
+        match action {
+            InsertChar(ch) => {
+                search.pattern.push(ch);
+                return Some(self.update_search());
+            }
+            BackwardDeleteChar => {
+                search.pattern.pop();
+                return Some(self.update_search());
+            }
+            Older => search.selection = (search.selection + 1).min(search.matches.len().saturating_sub(1)),
+            Newer => search.selection = search.selection.saturating_sub(1),
+            Accept => {
+                self.end_search(true);
+                return Some(self.do_action(&MiniBufferAction::Finish));
+            }
+            Edit => self.end_search(true),
+            Cancel => self.end_search(false),
+        }
+
+        Some(Ok(()))
+    }
+
+    // Prompt, line and cursor position shown while searching
+    fn search_line(&self, search: &HistorySearch) -> (String, String, usize) {
+        let failed = match search.matches.is_empty() && !search.pattern.is_empty() {
+            true => "failed ",
+            false => "",
+        };
+        let prompt = format!("{} ({}history search) '{}'", self.query, failed, search.pattern);
+        let line = search.selected().cloned().unwrap_or_default();
+
+        match line.find(&search.pattern) {
+            Some(start) if !search.pattern.is_empty() => {
+                let end = start + search.pattern.len();
+                let highlighted = format!("{}{}{}{}{}{}",
+                                          &line[..start],
+                                          crate::term::invert(),
+                                          &line[start..end],
+                                          crate::term::reset(),
+                                          crate::term::header_color(),
+                                          &line[end..]);
+                (prompt, highlighted, line[..start].chars().count())
+            }
+            _ => (prompt, line, 0),
+        }
+    }
+
     pub fn clear_line(&mut self) -> WResult<()> {
         self.input.clear();
         self.position = 0;
@@ -356,6 +618,176 @@ impl MiniBuffer {
         Ok(())
     }
 
+    // Byte position of the index-th character
+    fn char_pos(&self, index: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(index)
+            .map(|(pos, _)| pos)
+            .unwrap_or(self.input.len())
+    }
+
+    // Killed text goes into the kill ring, consecutive kills are joined
+    fn save_kill(&mut self, killed: &str, backward: bool, continued: bool) {
+        if killed.is_empty() {
+            return;
+        }
+
+        match (continued, self.kill_ring.last_mut()) {
+            (true, Some(last)) if backward => last.insert_str(0, killed),
+            (true, Some(last)) => last.push_str(killed),
+            _ => {
+                self.kill_ring.push(killed.to_string());
+                if self.kill_ring.len() > MAX_KILLS {
+                    self.kill_ring.remove(0);
+                }
+            }
+        }
+    }
+
+    fn kill(&mut self, start: usize, end: usize, continued: bool) {
+        let backward = start < self.position;
+        let killed = self.input.drain(start..end).collect::<String>();
+        self.save_kill(&killed, backward, continued);
+        self.position = start;
+    }
+
+    pub fn yank(&mut self) -> WResult<()> {
+        let index = self.kill_ring.len().checked_sub(1).ok_or(WError::NoneError)?;
+        let text = self.kill_ring[index].clone();
+
+        self.yanked = Some((self.position, index));
+        self.input.insert_str(self.position, &text);
+        self.position += text.len();
+        Ok(())
+    }
+
+    // Replaces the text just yanked with the previous kill
+    pub fn yank_pop(&mut self, yanked: Option<(usize, usize)>) -> WResult<()> {
+        let (start, index) = yanked.ok_or(WError::NoneError)?;
+        let len = self.kill_ring.len();
+        let index = (index + len - 1) % len;
+        let text = self.kill_ring[index].clone();
+
+        self.input.replace_range(start..self.position, &text);
+        self.position = start + text.len();
+        self.yanked = Some((start, index));
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> WResult<()> {
+        let (input, position) = self.undo.pop().ok_or(WError::NoneError)?;
+        self.input = input;
+        self.position = position;
+        Ok(())
+    }
+
+    pub fn transpose_chars(&mut self) {
+        let mut chars = self.input.chars().collect::<Vec<char>>();
+        let mut index = self.input[..self.position].chars().count();
+
+        if chars.len() < 2 || index == 0 {
+            return;
+        }
+        // At the end the last two characters are swapped
+        if index == chars.len() {
+            index -= 1;
+        }
+
+        chars.swap(index - 1, index);
+        self.input = chars.into_iter().collect();
+        self.position = self.char_pos(index + 1);
+    }
+
+    pub fn transpose_words(&mut self) {
+        let end2 = word_end(&self.input, self.position);
+        let start2 = word_start(&self.input, end2);
+        let start1 = word_start(&self.input, start2);
+        let end1 = word_end(&self.input, start1);
+
+        if start1 == start2 || end1 > start2 {
+            return;
+        }
+
+        self.input = format!("{}{}{}{}{}",
+                             &self.input[..start1],
+                             &self.input[start2..end2],
+                             &self.input[end1..start2],
+                             &self.input[start1..end1],
+                             &self.input[end2..]);
+        self.position = end2;
+    }
+
+    // From the cursor to the end of the word
+    pub fn change_case(&mut self, change: impl Fn(&str) -> String) {
+        let end = word_end(&self.input, self.position);
+        let word = change(&self.input[self.position..end]);
+
+        self.input.replace_range(self.position..end, &word);
+        self.position += word.len();
+    }
+
+    // Like in bash the edited line is used right away
+    pub fn edit_in_editor(&mut self) -> WResult<()> {
+        let (path, mut file) = create_tmp_file("wandex-minibuffer")?;
+        let written = file.write_all(self.input.as_bytes());
+        drop(file);
+
+        let edited = written
+            .map_err(WError::from)
+            .and_then(|_| self.run_editor(&path));
+        std::fs::remove_file(&path).ok();
+
+        let edited = match edited? {
+            Some(edited) => edited.trim_end().lines().collect::<Vec<_>>().join(" "),
+            None => return Ok(()),
+        };
+
+        // Like in bash, an emptied file runs nothing
+        if edited.trim().is_empty() {
+            return self.core.show_status("Nothing to run, the line was left as it was");
+        }
+
+        self.input = edited;
+        self.position = self.input.len();
+        self.do_action(&MiniBufferAction::Finish)
+    }
+
+    // Nothing is returned when the editor couldn't be started or failed,
+    // like after ":cq" in vim
+    fn run_editor(&mut self, path: &Path) -> WResult<Option<String>> {
+        let sender = self.core.get_sender();
+        // EDITOR can have arguments, like "code --wait"
+        let editor = std::env::var("EDITOR").unwrap_or("vi".into());
+        let mut args = crate::cmdline::split_words(&editor)?;
+        if args.is_empty() {
+            args.push("vi".to_string());
+        }
+        let cmd = args.remove(0);
+
+        sender.send(crate::widget::Events::InputEnabled(false))?;
+        self.core.screen.suspend().log();
+
+        let status = std::process::Command::new(&cmd).args(&args).arg(path).status();
+
+        self.core.screen.activate().log();
+        sender.send(crate::widget::Events::InputEnabled(true))?;
+
+        match status {
+            Ok(status) if !status.success() => {
+                self.core.show_status(&format!("\"{}\" failed, nothing was run", editor)).log();
+                return Ok(None);
+            }
+            Err(err) => {
+                self.core.show_status(&format!("Can't run \"{}\": {}", editor, err)).log();
+                return Ok(None);
+            }
+            Ok(_) => {}
+        }
+
+        Ok(Some(std::fs::read_to_string(path)?))
+    }
+
     pub fn input_finnished(&self) -> WResult<()> {
         return WError::popup_finished();
     }
@@ -375,6 +807,61 @@ impl MiniBuffer {
     }
 }
 
+// Only readable by us, with a name nobody can guess and take first
+fn create_tmp_file(prefix: &str) -> WResult<(PathBuf, std::fs::File)> {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::os::unix::fs::OpenOptionsExt;
+
+    loop {
+        let suffix = RandomState::new().build_hasher().finish();
+        let path = std::env::temp_dir().join(format!("{}-{:016x}", prefix, suffix));
+
+        match std::fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric()
+}
+
+// Where the word before pos starts, like M-b
+fn word_start(input: &str, pos: usize) -> usize {
+    input[..pos]
+        .trim_end_matches(|ch| !is_word_char(ch))
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| !is_word_char(*ch))
+        .map(|(i, ch)| i + ch.len_utf8())
+        .unwrap_or(0)
+}
+
+// Where the word after pos ends, like M-f
+fn word_end(input: &str, pos: usize) -> usize {
+    let after = &input[pos..];
+    let skipped = after.find(is_word_char).unwrap_or(after.len());
+    let word = &after[skipped..];
+    pos + skipped + word.find(|ch| !is_word_char(ch)).unwrap_or(word.len())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut first = true;
+    word.chars().fold(String::new(), |mut capitalized, ch| {
+        match first && is_word_char(ch) {
+            true => {
+                capitalized.extend(ch.to_uppercase());
+                first = false;
+            }
+            false => capitalized.extend(ch.to_lowercase()),
+        }
+        capitalized
+    })
+}
+
 pub fn find_bins(comp_name: &str) -> WResult<Vec<OsString>> {
     use osstrtools::OsStrTools;
 
@@ -485,20 +972,44 @@ impl Widget for MiniBuffer {
 
     fn get_drawlist(&self) -> WResult<String> {
         let (xpos, ypos) = self.get_coordinates()?.u16position();
+        let menu = match &self.completion {
+            Some(completion) => completion.render(xpos, ypos, self.get_coordinates()?.xsize_u()),
+            None => String::new(),
+        };
+
+        let (prompt, line) = match &self.search {
+            Some(search) => {
+                let (prompt, line, _) = self.search_line(search);
+                (prompt, line)
+            }
+            None => (self.query.clone(), self.input.clone()),
+        };
+
         Ok(format!(
-            "{}{}{}{}: {}",
+            "{}{}{}{}{}: {}",
+            menu,
             crate::term::goto_xy(xpos, ypos),
             termion::clear::CurrentLine,
             crate::term::header_color(),
-            self.query,
-            self.input
+            prompt,
+            line
         ))
     }
 
     fn on_key(&mut self, key: Key) -> WResult<()> {
         let prev_input = self.input.clone();
 
-        self.do_key(key)?;
+        match self.search_key(key).or_else(|| self.completion_key(key)) {
+            Some(result) => result?,
+            None => {
+                let position = self.position;
+                self.do_key(key)?;
+
+                if prev_input != self.input || position != self.position {
+                    self.update_completion();
+                }
+            }
+        }
 
         if self.continuous && prev_input != self.input {
             self.input_updated()?;
@@ -508,7 +1019,14 @@ impl Widget for MiniBuffer {
     }
 
     fn after_draw(&self) -> WResult<()> {
-        let cursor_pos = crate::term::string_len(&self.query) + ": ".len() + self.position;
+        let (prompt, position) = match &self.search {
+            Some(search) => {
+                let (prompt, _, position) = self.search_line(search);
+                (prompt, position)
+            }
+            None => (self.query.clone(), self.position),
+        };
+        let cursor_pos = crate::term::string_len(&prompt) + ": ".len() + position;
 
         let mut screen = self.core.screen()?;
         let ysize = screen.ysize()?;
@@ -532,6 +1050,33 @@ impl Acting for MiniBuffer {
     fn do_action(&mut self, action: &Self::Action) -> WResult<()> {
         use MiniBufferAction::*;
 
+        let (input, position) = (self.input.clone(), self.position);
+        let last_action = self.last_action.replace(*action);
+        let yanked = self.yanked.take();
+        let continued_kill = matches!(last_action,
+                                      Some(KillLine) | Some(KillWord) | Some(DeleteWord));
+
+        match action {
+            Undo => return self.undo(),
+            YankPop => self.yank_pop(yanked)?,
+            _ => self.edit(action, continued_kill)?,
+        }
+
+        // A run of typed characters is undone in one go
+        let typing = matches!((last_action, action), (Some(InsertChar(_)), InsertChar(_)));
+
+        if self.input != input && !typing {
+            self.undo.push((input, position));
+        }
+
+        Ok(())
+    }
+}
+
+impl MiniBuffer {
+    fn edit(&mut self, action: &MiniBufferAction, continued_kill: bool) -> WResult<()> {
+        use MiniBufferAction::*;
+
         match action {
             InsertChar(ch) => {
                 self.input.insert(self.position, *ch);
@@ -547,8 +1092,10 @@ impl Acting for MiniBuffer {
                 self.input_cancelled()?
             }
             Finish => {
-                if self.input != "" {
-                    self.history.add(&self.query, &self.input);
+                // Like HISTCONTROL=ignorespace in bash
+                if !self.input.is_empty() && !self.input.starts_with(' ') {
+                    let size = self.core.config().history_size;
+                    self.history.add(&self.query, &self.input, size).log();
                 }
                 self.input_finnished()?
             }
@@ -576,11 +1123,74 @@ impl Acting for MiniBuffer {
             }
             HistoryUp => self.history_up()?,
             HistoryDown => self.history_down()?,
-            ClearLine => self.clear_line()?,
-            DeleteWord => self.delete_word()?,
+            ClearLine => {
+                let line = self.input.clone();
+                self.clear_line()?;
+                self.save_kill(&line, true, false);
+            }
+            DeleteWord => {
+                let (input, end) = (self.input.clone(), self.position);
+                self.delete_word()?;
+                self.save_kill(&input[self.position..end], true, continued_kill);
+            }
             CursorToStart => self.position = 0,
             CursorToEnd => self.position = self.input.len(),
+            ForwardWord => self.position = word_end(&self.input, self.position),
+            BackwardWord => self.position = word_start(&self.input, self.position),
+            KillLine => self.kill(self.position, self.input.len(), continued_kill),
+            KillWord => {
+                let end = word_end(&self.input, self.position);
+                self.kill(self.position, end, continued_kill)
+            }
+            Yank => self.yank()?,
+            TransposeChars => self.transpose_chars(),
+            TransposeWords => self.transpose_words(),
+            UpcaseWord => self.change_case(str::to_uppercase),
+            DowncaseWord => self.change_case(str::to_lowercase),
+            CapitalizeWord => self.change_case(capitalize),
+            EditInEditor => self.edit_in_editor()?,
+            HistorySearch => self.history_search()?,
+            // Handled in do_action
+            Undo | YankPop => {}
         }
         Ok(())
     }
 }
+
+#[test]
+fn test_parse_history_entry() {
+    let entry = Entry::parse("exec:ls -l a:b").unwrap();
+    assert_eq!((entry.htype.as_str(), entry.line.as_str(), entry.dir), ("exec", "ls -l a:b", None));
+
+    let entry = Entry::parse("exec\t/home/user\tgrep \"a\tb\" x:y").unwrap();
+    assert_eq!((entry.htype.as_str(), entry.line.as_str(), entry.dir.as_deref()),
+               ("exec", "grep \"a\tb\" x:y", Some("/home/user")));
+    assert_eq!(entry.to_line(), "exec\t/home/user\tgrep \"a\tb\" x:y\n");
+
+    assert!(Entry::parse("exec\t/home/user").is_none());
+    assert!(Entry::parse("no separator").is_none());
+}
+
+#[test]
+fn test_tidy_history() {
+    let entry = |htype: &str, line: &str| Entry {
+        htype: htype.to_string(),
+        line: line.to_string(),
+        dir: None,
+    };
+    let mut history = History::new();
+    history.entries = vec![entry("exec", "a"),
+                           entry("exec", "b"),
+                           entry("search", "a"),
+                           entry("exec", "a"),
+                           entry("exec", "c"),
+                           entry("exec", "d")];
+
+    // Duplicates are moved to their last use, then the oldest are dropped
+    history.tidy(3);
+    let lines = history.entries
+        .iter()
+        .map(|entry| format!("{}:{}", entry.htype, entry.line))
+        .collect::<Vec<_>>();
+    assert_eq!(lines, vec!["search:a", "exec:a", "exec:c", "exec:d"]);
+}
@@ -0,0 +1,366 @@
+use strum::IntoEnumIterator;
+use termion::event::Key;
+use unicode_width::UnicodeWidthStr;
+
+use crate::coordinates::Coordinates;
+use crate::fail::{WError, WResult};
+use crate::keybind::*;
+use crate::term;
+use crate::widget::{Widget, WidgetCore};
+
+// What the file browser runs once an entry is picked
+#[derive(Clone, Copy, Debug)]
+pub enum Command {
+    Movement(Movement),
+    FileBrowser(FileBrowserAction),
+    FileList(FileListAction),
+    Tab(TabAction),
+    Media(MediaAction),
+    // Only works in its own popup or the minibuffer
+    Popup,
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub section: &'static str,
+    pub keys: String,
+    pub command: Command,
+}
+
+fn section_entries<T>(bindings: &Bindings<T>, command: fn(T) -> Command) -> Vec<Entry>
+where
+    T: BindingSection + IntoEnumIterator,
+    Bindings<T>: Default,
+{
+    T::iter()
+        .map(|action| {
+            let action = action.as_default();
+            let keys = bindings
+                .keys(&action)
+                .iter()
+                .map(|keys| keys_to_string(keys))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            Entry {
+                name: action.to_string(),
+                section: T::section(),
+                keys,
+                command: command(action),
+            }
+        })
+        .collect()
+}
+
+fn popup<T>(_action: T) -> Command {
+    Command::Popup
+}
+
+// Listed by their command line, RunCommand itself means nothing
+fn command_entries(keybinds: &KeyBinds) -> Vec<Entry> {
+    keybinds
+        .commands
+        .iter()
+        .enumerate()
+        .map(|(n, command)| {
+            let keys = keybinds
+                .filebrowser
+                .keys_where(|action| matches!(action, FileBrowserAction::RunCommand(bound) if *bound == n))
+                .iter()
+                .map(|keys| keys_to_string(keys))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            Entry {
+                name: command.cmd.clone(),
+                section: "commands",
+                keys,
+                command: Command::FileBrowser(FileBrowserAction::RunCommand(n)),
+            }
+        })
+        .collect()
+}
+
+// Every action there is, roughly in the order of the keys file
+pub fn entries(keybinds: &KeyBinds) -> Vec<Entry> {
+    let filebrowser = section_entries(&keybinds.filebrowser, Command::FileBrowser)
+        .into_iter()
+        .filter(|entry| !matches!(entry.command, Command::FileBrowser(FileBrowserAction::RunCommand(_))))
+        .collect();
+
+    vec![filebrowser,
+         command_entries(keybinds),
+         section_entries(&keybinds.filelist, Command::FileList),
+         section_entries(&keybinds.movement, Command::Movement),
+         section_entries(&keybinds.tab, Command::Tab),
+         section_entries(&keybinds.media, Command::Media),
+         section_entries(&keybinds.bookmark, popup),
+         section_entries(&keybinds.process, popup),
+         section_entries(&keybinds.minibuffer, popup),
+         section_entries(&keybinds.completion, popup),
+         section_entries(&keybinds.historysearch, popup),
+         section_entries(&keybinds.fold, popup),
+         section_entries(&keybinds.log, popup),
+         section_entries(&keybinds.quickaction, popup),
+         section_entries(&keybinds.basket, popup),
+         section_entries(&keybinds.tag, popup),
+         section_entries(&keybinds.tagged, popup),
+         section_entries(&keybinds.jumplist, popup),
+         section_entries(&keybinds.palette, popup)]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// Matches at the start of the name beat those anywhere in it, and those
+// beat the filter's characters showing up in order
+fn match_tier(entry: &Entry, filter: &str) -> Option<usize> {
+    let name = entry.name.to_lowercase();
+    let text = format!("{} {}", name, entry.section);
+
+    if name.starts_with(filter) {
+        Some(0)
+    } else if text.contains(filter) {
+        Some(1)
+    } else {
+        let mut chars = text.chars();
+        match filter.chars().all(|fc| chars.any(|c| c == fc)) {
+            true => Some(2),
+            false => None,
+        }
+    }
+}
+
+pub struct PaletteView {
+    core: WidgetCore,
+    entries: Vec<Entry>,
+    filter: String,
+    // Positions in entries, best match first
+    visible: Vec<usize>,
+    selection: usize,
+    picked: Option<Entry>,
+    // Only bound keys, grouped by section and in the order of the keys file
+    help: bool,
+}
+
+impl PaletteView {
+    pub fn new(core: &WidgetCore) -> PaletteView {
+        let entries = entries(&core.config().keybinds);
+        let visible = (0..entries.len()).collect();
+
+        PaletteView {
+            core: core.clone(),
+            entries,
+            filter: String::new(),
+            visible,
+            selection: 0,
+            picked: None,
+            help: false,
+        }
+    }
+
+    // The keys that work in the given sections
+    pub fn help(core: &WidgetCore, sections: &[&str]) -> PaletteView {
+        let mut palette = PaletteView::new(core);
+
+        palette.entries.retain(|entry| sections.contains(&entry.section) && !entry.keys.is_empty());
+        palette.entries.sort_by_key(|entry| {
+            sections.iter().position(|section| *section == entry.section)
+        });
+        palette.visible = (0..palette.entries.len()).collect();
+        palette.help = true;
+        palette
+    }
+
+    pub fn pick(&mut self) -> WResult<Option<Entry>> {
+        self.picked = None;
+
+        match self.popup() {
+            Ok(_) | Err(WError::PopupFinished) => {}
+            Err(err) => return Err(err),
+        }
+
+        Ok(self.picked.take())
+    }
+
+    fn update_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let mut visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| Some((match_tier(entry, &filter)?, i)))
+            .collect::<Vec<_>>();
+
+        // Help keeps the sections together
+        if !self.help {
+            visible.sort();
+        }
+        self.visible = visible.into_iter().map(|(_, i)| i).collect();
+        self.selection = 0;
+    }
+
+    // Positions in visible, with None for the section headers of help
+    fn rows(&self) -> Vec<Option<usize>> {
+        let mut rows = vec![];
+        let mut section = None;
+
+        for (pos, &i) in self.visible.iter().enumerate() {
+            if self.help && section != Some(self.entries[i].section) {
+                section = Some(self.entries[i].section);
+                rows.push(None);
+            }
+            rows.push(Some(pos));
+        }
+
+        rows
+    }
+
+    fn render_line(&self, entry: &Entry, name_width: usize, selected: bool) -> String {
+        let xsize = self.core.coordinates.xsize_u();
+        let name_color = match entry.command {
+            Command::Popup => term::color_light_black(),
+            _ => term::normal_color(),
+        };
+        // Long names, like user commands, would push the keys out of view
+        let name = match entry.name.width() > name_width {
+            true => format!("{}…", term::sized_string(&entry.name, name_width as u16)),
+            false => entry.name.clone(),
+        };
+        let line = format!("{}{}{:name_width$} {}{:keys_width$} {}{}",
+                           match selected {
+                               true => term::invert(),
+                               false => String::new(),
+                           },
+                           name_color,
+                           name,
+                           term::highlight_color(),
+                           entry.keys,
+                           term::color_light_black(),
+                           entry.section,
+                           name_width = name_width,
+                           keys_width = xsize / 4);
+
+        term::sized_string_u(&line, xsize)
+    }
+}
+
+impl Widget for PaletteView {
+    fn get_core(&self) -> WResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> WResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+    fn set_coordinates(&mut self, coordinates: &Coordinates) -> WResult<()> {
+        self.core.coordinates = coordinates.clone();
+        Ok(())
+    }
+    fn render_header(&self) -> WResult<String> {
+        let title = match self.help {
+            true => "Help",
+            false => "Command palette",
+        };
+
+        Ok(format!("{}: {} of {} actions",
+                   title,
+                   self.visible.len(),
+                   self.entries.len()))
+    }
+    fn render_footer(&self) -> WResult<String> {
+        Ok(format!("> {}", self.filter))
+    }
+    fn refresh(&mut self) -> WResult<()> {
+        Ok(())
+    }
+    fn get_drawlist(&self) -> WResult<String> {
+        let (xpos, ypos) = self.core.coordinates.u16position();
+        let height = self.core.coordinates.ysize_u();
+        let rows = self.rows();
+        let selected_row = rows
+            .iter()
+            .position(|row| *row == Some(self.selection))
+            .unwrap_or(0);
+        let offset = (selected_row + 1).saturating_sub(height);
+        let name_width = self
+            .entries
+            .iter()
+            .map(|entry| entry.name.width())
+            .max()
+            .unwrap_or(0)
+            .min(self.core.coordinates.xsize_u() / 2);
+
+        Ok((0..height)
+           .map(|row| {
+               let line = match rows.get(row + offset) {
+                   Some(Some(pos)) => self.render_line(&self.entries[self.visible[*pos]],
+                                                       name_width,
+                                                       *pos == self.selection),
+                   Some(None) => {
+                       let section = rows[row + offset + 1]
+                           .map(|pos| self.entries[self.visible[pos]].section)
+                           .unwrap_or("");
+                       format!("{}[{}]", term::header_color(), section)
+                   }
+                   None => String::new(),
+               };
+
+               format!("{}{}{}{}",
+                       term::goto_xy(xpos, ypos + row as u16),
+                       termion::clear::CurrentLine,
+                       line,
+                       term::reset())
+           })
+           .collect())
+    }
+    fn on_key(&mut self, key: Key) -> WResult<()> {
+        // Characters that close, like "?" toggling the help, only do so
+        // while nothing is typed. Otherwise they're part of the filter.
+        let closes = matches!(self.search_in().get(key), Some(PaletteAction::Close));
+
+        match key {
+            Key::Char(ch) if closes && !ch.is_control() && !self.filter.is_empty() => {
+                self.do_action(&PaletteAction::InsertChar(ch))
+            }
+            _ => self.do_key(key),
+        }
+    }
+}
+
+impl Acting for PaletteView {
+    type Action = PaletteAction;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        self.core.config().keybinds.palette
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> WResult<()> {
+        use PaletteAction::*;
+
+        match action {
+            InsertChar(ch) => {
+                self.filter.push(*ch);
+                self.update_filter();
+            }
+            BackwardDeleteChar => {
+                self.filter.pop();
+                self.update_filter();
+            }
+            Prev => self.selection = self.selection.saturating_sub(1),
+            Next => {
+                self.selection = (self.selection + 1).min(self.visible.len().saturating_sub(1))
+            }
+            Run => {
+                self.picked = self
+                    .visible
+                    .get(self.selection)
+                    .map(|&i| self.entries[i].clone());
+                WError::popup_finished()?
+            }
+            Close => WError::popup_finished()?,
+        }
+
+        Ok(())
+    }
+}
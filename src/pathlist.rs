@@ -0,0 +1,218 @@
+use termion::event::Key;
+
+use std::fmt::Debug;
+
+use crate::coordinates::Coordinates;
+use crate::fail::{ErrorLog, WError, WResult};
+use crate::files::{File, Files};
+use crate::fscache::FsCache;
+use crate::keybind::{Acting, BindingSection, Bindings, KeyBinds, Movement};
+use crate::listview::{FileSource, Listable, ListView};
+use crate::term;
+use crate::widget::{Widget, WidgetCore};
+
+// What to do with files from the list once it's closed
+pub enum FilePick {
+    Goto(File),
+    QuickActions(Vec<File>),
+    ExecCmd(Vec<File>),
+}
+
+// How a list action ends
+pub enum Outcome<P> {
+    Close,
+    Pick(P),
+    // Entries were removed, so the list is built again
+    Reload,
+}
+
+// Paths from all over the place, like the basket or the tagged files
+pub trait PathList {
+    type Action;
+    type Pick;
+
+    // Unbound keys go to the list, so files can be selected
+    const SELECTABLE: bool = false;
+
+    // With the names as they should be shown
+    fn files(&self) -> WResult<Vec<File>>;
+    fn header(&self, len: usize) -> String;
+    fn bindings(keybinds: KeyBinds) -> Bindings<Self::Action>;
+    // Run when moving right
+    fn goto_action() -> Self::Action;
+    fn do_action(&mut self,
+                 action: &Self::Action,
+                 list: &ListView<Files>) -> WResult<Outcome<Self::Pick>>;
+
+    fn selection(&self) -> usize {
+        0
+    }
+
+    // Shown after the path of existing files
+    fn footer_info(&self, _file: &File) -> String {
+        String::new()
+    }
+}
+
+// Shown as a virtual directory with the full paths as file names, so it
+// looks just like any other file list
+pub struct PathListView<L: PathList> {
+    core: WidgetCore,
+    list: ListView<Files>,
+    cache: FsCache,
+    source: L,
+    picked: Option<L::Pick>,
+}
+
+impl<L: PathList> PathListView<L> {
+    pub fn new(core: &WidgetCore, cache: FsCache, source: L) -> WResult<PathListView<L>> {
+        let mut list = PathListView::build_list(core, &cache, &source)?;
+        list.set_selection(source.selection());
+
+        Ok(PathListView {
+            core: core.clone(),
+            list,
+            cache,
+            source,
+            picked: None,
+        })
+    }
+
+    fn build_list(core: &WidgetCore, cache: &FsCache, source: &L) -> WResult<ListView<Files>> {
+        let mut listing = Files::default();
+        listing.show_hidden = true;
+        listing.files = source.files()?;
+        listing.recalculate_len();
+
+        ListView::builder(core.clone(), FileSource::Files(listing))
+            .with_cache(cache.clone())
+            .build()
+    }
+
+    fn reload(&mut self) -> WResult<()> {
+        let selection = self.list.get_selection();
+        self.list = PathListView::build_list(&self.core, &self.cache, &self.source)?;
+
+        // Nothing left to show
+        if self.list.len() == 0 {
+            return WError::popup_finished();
+        }
+
+        self.list
+            .set_selection(selection.min(self.list.len().saturating_sub(1)));
+        self.core.clear().log();
+        Ok(())
+    }
+
+}
+
+impl<L> PathListView<L>
+where
+    L: PathList,
+    L::Action: BindingSection + Debug,
+    Bindings<L::Action>: Default,
+{
+    pub fn pick(&mut self) -> WResult<Option<L::Pick>> {
+        self.picked = None;
+
+        match self.popup() {
+            Ok(_) | Err(WError::PopupFinished) => {}
+            Err(err) => return Err(err),
+        }
+
+        Ok(self.picked.take())
+    }
+}
+
+// The selection, or the file under the cursor
+pub fn selected_or_current(list: &ListView<Files>) -> Vec<File> {
+    let selected = list
+        .content
+        .iter_files()
+        .filter(|file| file.is_selected())
+        .cloned()
+        .collect::<Vec<File>>();
+
+    match selected.len() {
+        0 => vec![list.selected_file().clone()],
+        _ => selected,
+    }
+}
+
+impl<L> Widget for PathListView<L>
+where
+    L: PathList,
+    L::Action: BindingSection + Debug,
+    Bindings<L::Action>: Default,
+{
+    fn get_core(&self) -> WResult<&WidgetCore> {
+        Ok(&self.core)
+    }
+    fn get_core_mut(&mut self) -> WResult<&mut WidgetCore> {
+        Ok(&mut self.core)
+    }
+    fn set_coordinates(&mut self, coordinates: &Coordinates) -> WResult<()> {
+        self.core.coordinates = coordinates.clone();
+        self.list.set_coordinates(coordinates)
+    }
+    fn render_header(&self) -> WResult<String> {
+        Ok(self.source.header(self.list.len()))
+    }
+    fn render_footer(&self) -> WResult<String> {
+        let xsize = self.core.coordinates.xsize_u();
+        let file = self.list.selected_file();
+
+        let footer = match file.path.exists() {
+            true => format!("{}{}", file.path.to_string_lossy(), self.source.footer_info(file)),
+            false => format!("{}{} (missing)", term::color_red(), file.path.to_string_lossy()),
+        };
+
+        Ok(term::sized_string_u(&footer, xsize))
+    }
+    fn refresh(&mut self) -> WResult<()> {
+        self.list.refresh()
+    }
+    fn get_drawlist(&self) -> WResult<String> {
+        self.list.get_drawlist()
+    }
+    fn on_key(&mut self, key: Key) -> WResult<()> {
+        match self.do_key(key) {
+            Err(WError::WidgetUndefinedKeyError { .. }) if L::SELECTABLE => {
+                Widget::on_key(&mut self.list, key)
+            }
+            result => result,
+        }
+    }
+}
+
+impl<L> Acting for PathListView<L>
+where
+    L: PathList,
+    L::Action: BindingSection + Debug,
+    Bindings<L::Action>: Default,
+{
+    type Action = L::Action;
+
+    fn search_in(&self) -> Bindings<Self::Action> {
+        L::bindings(self.core.config().keybinds)
+    }
+
+    fn movement(&mut self, movement: &Movement) -> WResult<()> {
+        match movement {
+            Movement::Left => WError::popup_finished(),
+            Movement::Right => self.do_action(&L::goto_action()),
+            _ => self.list.movement(movement),
+        }
+    }
+
+    fn do_action(&mut self, action: &Self::Action) -> WResult<()> {
+        match self.source.do_action(action, &self.list)? {
+            Outcome::Close => WError::popup_finished(),
+            Outcome::Pick(pick) => {
+                self.picked = Some(pick);
+                WError::popup_finished()
+            }
+            Outcome::Reload => self.reload(),
+        }
+    }
+}
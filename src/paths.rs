@@ -1,6 +1,8 @@
 use dirs_2;
 
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
 
 use crate::fail::{WError, WResult};
 
@@ -54,6 +56,12 @@ pub fn tagfile_path() -> WResult<PathBuf> {
     Ok(tagfile_path)
 }
 
+pub fn frecency_path() -> WResult<PathBuf> {
+    let mut frecency_path = wandex_path()?;
+    frecency_path.push("frecency");
+    Ok(frecency_path)
+}
+
 pub fn history_path() -> WResult<PathBuf> {
     let mut history_path = wandex_path()?;
     history_path.push("history");
@@ -71,3 +79,142 @@ pub fn previewers_path() -> WResult<PathBuf> {
     previewers_path.push("previewers");
     Ok(previewers_path)
 }
+
+pub fn icons_path() -> WResult<PathBuf> {
+    let mut icons_path = wandex_path()?;
+    icons_path.push("icons");
+    Ok(icons_path)
+}
+
+pub fn sessions_path() -> WResult<PathBuf> {
+    let mut sessions_path = wandex_path()?;
+    sessions_path.push("sessions");
+    Ok(sessions_path)
+}
+
+// Writes a temporary file next to path and renames it, so a crash or a
+// full disk never leaves a half written file behind
+pub fn write_atomic(path: &Path, content: &[u8]) -> WResult<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Threads of one process can write the same file at the same time
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    let name = path.file_name().ok_or(WError::NoneError)?.to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{}.{}.{}.tmp",
+                                               name,
+                                               std::process::id(),
+                                               WRITES.fetch_add(1, Ordering::Relaxed)));
+
+    std::fs::write(&tmp_path, content)
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .map_err(|err| {
+            std::fs::remove_file(&tmp_path).ok();
+            err.into()
+        })
+}
+
+// Values in the line based files above can't contain line breaks
+pub fn escape_value(value: &str) -> String {
+    value.chars().fold(String::new(), |mut escaped, ch| {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch => escaped.push(ch),
+        }
+        escaped
+    })
+}
+
+pub fn unescape_value(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        let escaped = match (ch, chars.peek()) {
+            ('\\', Some('\\')) => '\\',
+            ('\\', Some('n')) => '\n',
+            ('\\', Some('r')) => '\r',
+            ('\\', Some('t')) => '\t',
+            _ => {
+                unescaped.push(ch);
+                continue;
+            }
+        };
+
+        chars.next();
+        unescaped.push(escaped);
+    }
+
+    unescaped
+}
+
+// Like escape_value, but bytes that aren't UTF-8 are kept as "\xff", so
+// any path comes back exactly as it was
+pub fn escape_path(path: &Path) -> String {
+    let mut escaped = String::new();
+    let mut rest = path.as_os_str().as_bytes();
+
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                escaped += &escape_value(valid);
+                return escaped;
+            }
+            Err(err) => {
+                let (valid, invalid) = rest.split_at(err.valid_up_to());
+                let len = err.error_len().unwrap_or(invalid.len());
+
+                escaped += &escape_value(&String::from_utf8_lossy(valid));
+                for byte in &invalid[..len] {
+                    escaped += &format!("\\x{:02x}", byte);
+                }
+                rest = &invalid[len..];
+            }
+        }
+    }
+}
+
+pub fn unescape_path(value: &str) -> PathBuf {
+    let bytes = value.as_bytes();
+    let mut unescaped = vec![];
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let hex = bytes.get(pos + 2..pos + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        let (byte, len) = match (bytes[pos], bytes.get(pos + 1), hex) {
+            (b'\\', Some(b'\\'), _) => (b'\\', 2),
+            (b'\\', Some(b'n'), _) => (b'\n', 2),
+            (b'\\', Some(b'r'), _) => (b'\r', 2),
+            (b'\\', Some(b't'), _) => (b'\t', 2),
+            (b'\\', Some(b'x'), Some(byte)) => (byte, 4),
+            (byte, _, _) => (byte, 1),
+        };
+
+        unescaped.push(byte);
+        pos += len;
+    }
+
+    PathBuf::from(OsString::from_vec(unescaped))
+}
+
+#[test]
+fn test_escape_path() {
+    let path = PathBuf::from(OsString::from_vec(b"/tmp/a\\xb\n\xff\xfe\xc3\xa4".to_vec()));
+    assert_eq!(escape_path(&path), "/tmp/a\\\\xb\\n\\xff\\xfe\u{e4}");
+    assert_eq!(unescape_path(&escape_path(&path)), path);
+    assert_eq!(unescape_path("/tmp/\\xzz"), PathBuf::from("/tmp/\\xzz"));
+}
+
+#[test]
+fn test_escape_value() {
+    let value = "a\nb\tc\\n\\";
+    assert_eq!(escape_value(value), "a\\nb\\tc\\\\n\\\\");
+    assert_eq!(unescape_value(&escape_value(value)), value);
+    assert_eq!(unescape_value("C:\\x"), "C:\\x");
+}
@@ -319,6 +319,10 @@ impl Previewer {
         self.file.as_ref()
     }
 
+    pub fn shows_media(&self) -> bool {
+        matches!(self.widget.widget(), Ok(PreviewWidget::MediaView(_)))
+    }
+
     pub fn cancel_animation(&self) -> WResult<()> {
         Ok(self.animator.set_stale()?)
     }
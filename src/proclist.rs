@@ -1,15 +1,17 @@
 use std::ffi::OsString;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Write};
 use std::os::unix::ffi::OsStrExt;
 use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
 
 use async_value::Stale;
-use osstrtools::{OsStrConcat, OsStrTools, OsStringTools};
+use osstrtools::OsStringTools;
 use parking_lot::Mutex;
 use termion::event::Key;
+use termion::input::TermRead;
 use unicode_width::UnicodeWidthStr;
 
 use crate::coordinates::Coordinates;
@@ -39,97 +41,84 @@ pub struct Cmd {
     pub vars: Option<Vec<(OsString, OsString)>>,
     pub short_cmd: Option<String>,
     pub cwd: File,
+    // The file under the cursor
+    pub cwd_file: Option<File>,
+    pub prev_cwd: Option<File>,
     pub cwd_files: Option<Vec<File>>,
     pub tab_files: Option<Vec<Vec<File>>>,
     pub tab_paths: Option<Vec<File>>,
+    // Typed in before running, for "$i"
+    pub input: Option<String>,
 }
 
 impl Cmd {
-    fn process(&mut self) -> Vec<OsString> {
-        // Split the string now, so inserted files aren't screwed up by substitutions
-        let cmd = self
-            .cmd
-            .split(" ")
-            .into_iter()
-            .map(|s| s.to_os_string())
-            .collect();
-
-        let cmd = self.substitute_cwd_files(cmd);
-        let cmd = self.substitute_tab_files(cmd);
-        let cmd = self.substitute_tab_paths(cmd);
+    // Paths a pattern like "$s" or "$0s" stands for, before modifiers
+    fn pattern_paths(&self, base: &str) -> Option<Vec<PathBuf>> {
+        let paths = |files: &Vec<File>| files.iter().map(|file| file.path.clone()).collect();
+
+        let paths = match base {
+            "s" => paths(self.cwd_files.as_ref()?),
+            "f" => vec![self.cwd_file.as_ref()?.path.clone()],
+            "d" => vec![self.cwd.path.clone()],
+            "p" => vec![self.prev_cwd.as_ref()?.path.clone()],
+            "t" => crate::files::tagged_paths().ok()?,
+            "b" => crate::basket::paths().ok()?,
+            _ => match base.strip_suffix('s') {
+                Some(tab) => paths(self.tab_files.as_ref()?.get(tab.parse::<usize>().ok()?)?),
+                None => vec![self.tab_paths.as_ref()?.get(base.parse::<usize>().ok()?)?.path.clone()],
+            },
+        };
 
-        cmd
+        Some(paths)
     }
 
-    fn perform_substitution(
-        &self,
-        cmd: Vec<OsString>,
-        pat: &str,
-        files: Vec<File>,
-    ) -> Vec<OsString> {
-        if !self.cmd.contains(pat) {
-            return cmd;
+    // Patterns can be followed by zsh style modifiers, like "$f:t:r"
+    fn lookup(&self, name: &str) -> Option<Vec<OsString>> {
+        // Not a path, so it's left as it was typed
+        if name == "i" {
+            return Some(vec![OsString::from(self.input.as_ref()?)]);
         }
 
-        let files = files
+        let mut parts = name.split(':');
+        let paths = self.pattern_paths(parts.next()?)?;
+        let modifiers = parts.collect::<Vec<&str>>();
+
+        let paths = paths
             .into_iter()
-            .map(|file|
-                 // strip out the cwd part to make path shorter
-                 file.strip_prefix(&self.cwd)
-                 .into_os_string()
-                 // escape single quotes so file names with them work
-                 .escape_single_quote())
-            .collect::<Vec<OsString>>();
-
-        cmd.into_iter()
-            .map(|part| {
-                // If this part isn't the pattern, just return it as is
-                match part != pat {
-                    true => part,
-                    false => part
-                        .splice(pat, &files)
-                        .assemble_with_sep_and_wrap(" ", "'"),
+            .map(|path| {
+                let path = modifiers.iter().try_fold(path, |path, modifier| {
+                    let modified = match *modifier {
+                        "a" => path,
+                        "t" => PathBuf::from(path.file_name()?),
+                        "h" => path.parent()?.to_path_buf(),
+                        "r" => path.with_extension(""),
+                        "e" => PathBuf::from(path.extension().unwrap_or_default()),
+                        _ => return None,
+                    };
+                    Some(modified)
+                })?;
+
+                // Files in the current directory are shortened, unless
+                // they are wanted absolute
+                let path = match (modifiers.contains(&"a"), path.strip_prefix(&self.cwd.path)) {
+                    (false, Ok(relative)) if relative != Path::new("") => relative.to_path_buf(),
+                    _ => path,
+                };
+
+                // Don't let names like "-rf" pass as options
+                match path.as_os_str().as_bytes().starts_with(b"-") {
+                    true => Some(Path::new("./").join(path).into_os_string()),
+                    false => Some(path.into_os_string()),
                 }
             })
-            .collect()
-    }
+            .collect::<Option<Vec<OsString>>>()?;
 
-    fn substitute_cwd_files(&mut self, cmd: Vec<OsString>) -> Vec<OsString> {
-        if self.cwd_files.is_none() {
-            return cmd;
-        }
-        let files = self.cwd_files.take().unwrap();
-        self.perform_substitution(cmd, "$s", files)
+        Some(paths)
     }
 
-    fn substitute_tab_files(&mut self, cmd: Vec<OsString>) -> Vec<OsString> {
-        if self.tab_files.is_none() {
-            return cmd;
-        }
-        let tab_files = self.tab_files.take().unwrap();
-
-        tab_files
-            .into_iter()
-            .enumerate()
-            .fold(cmd, |cmd, (i, tab_files)| {
-                let tab_files_pat = String::from(format!("${}s", i));
-                self.perform_substitution(cmd, &tab_files_pat, tab_files)
-            })
-    }
-
-    fn substitute_tab_paths(&mut self, cmd: Vec<OsString>) -> Vec<OsString> {
-        if self.tab_paths.is_none() {
-            return cmd;
-        }
-        let tab_paths = self.tab_paths.take().unwrap();
-
-        tab_paths
-            .into_iter()
-            .enumerate()
-            .fold(cmd, |cmd, (i, tab_path)| {
-                let tab_path_pat = String::from(format!("${}", i));
-                self.perform_substitution(cmd, &tab_path_pat, vec![tab_path])
-            })
+    // The shell splits the command, only the patterns are replaced here
+    fn process(&mut self) -> OsString {
+        crate::shell::substitute(&self.cmd, |name| self.lookup(name))
     }
 }
 
@@ -231,18 +220,20 @@ impl Listable for ListView<Vec<Process>> {
     }
 }
 
+// A typed command ending with "!" runs in the foreground, without the "!"
+pub fn foreground(cmd: &str) -> (&str, bool) {
+    match cmd.strip_suffix('!') {
+        Some(cmd) => (cmd, true),
+        None => (cmd, false),
+    }
+}
+
 impl ListView<Vec<Process>> {
-    fn run_proc_subshell(&mut self, mut cmd: Cmd) -> WResult<()> {
+    fn run_proc_subshell(&mut self, mut cmd: Cmd, fg: bool, wait: bool) -> WResult<()> {
         let shell = std::env::var("SHELL").unwrap_or("sh".into());
         let home = crate::paths::home_path()?.into_os_string();
-        let fg = cmd.cmd.as_bytes().ends_with(b"!");
 
-        if fg {
-            // Remove that last !
-            cmd.cmd = cmd.cmd.trim_end("!");
-        }
-
-        let cmd_args = cmd.process().concat(" ");
+        let cmd_args = cmd.process();
 
         // Nicer for display
         let short = "~";
@@ -264,7 +255,7 @@ impl ListView<Vec<Process>> {
         if !fg {
             self.run_proc_raw(cmd)
         } else {
-            self.run_proc_raw_fg(cmd).log();
+            self.run_proc_raw_fg(cmd, wait).log();
 
             // Command might fail/return early. do this here
             self.core.screen.reset()?;
@@ -323,7 +314,7 @@ impl ListView<Vec<Process>> {
         Ok(())
     }
 
-    fn run_proc_raw_fg(&mut self, cmd: Cmd) -> WResult<()> {
+    fn run_proc_raw_fg(&mut self, cmd: Cmd, wait: bool) -> WResult<()> {
         let real_cmd = cmd.cmd;
         let short_cmd = cmd
             .short_cmd
@@ -338,7 +329,13 @@ impl ListView<Vec<Process>> {
         self.core.screen.reset()?;
         self.core.screen.suspend()?;
 
-        match Command::new(real_cmd).args(args).status() {
+        let status = Command::new(real_cmd).args(args).status();
+
+        if wait {
+            self.wait_for_key().log();
+        }
+
+        match status {
             Ok(status) => {
                 let color_success = if status.success() {
                     format!("{}successfully", term::color_green())
@@ -380,6 +377,15 @@ impl ListView<Vec<Process>> {
         Ok(())
     }
 
+    // Input isn't read while a key is handled, so stdin is free here
+    fn wait_for_key(&mut self) -> WResult<()> {
+        self.core.screen.write_str("\nPress any key to continue")?;
+        self.core.screen.flush()?;
+        self.core.screen.activate_raw_mode()?;
+        std::io::stdin().keys().next().transpose()?;
+        Ok(())
+    }
+
     fn kill_proc(&mut self) -> WResult<()> {
         let proc = self.selected_proc().ok_or(WError::NoneError)?;
         proc.handle.lock().kill()?;
@@ -531,8 +537,16 @@ impl ProcView {
         self.hbox.get_textview()
     }
 
-    pub fn run_proc_subshell(&mut self, cmd: Cmd) -> WResult<()> {
-        self.get_listview_mut().run_proc_subshell(cmd)?;
+    // Foreground commands take over the terminal until they quit
+    pub fn run_proc_subshell(&mut self, cmd: Cmd, fg: bool) -> WResult<()> {
+        self.get_listview_mut().run_proc_subshell(cmd, fg, false)?;
+        Ok(())
+    }
+
+    // Like run_proc_subshell, but the output of foreground commands stays
+    // until a key is pressed
+    pub fn run_proc_subshell_wait(&mut self, cmd: Cmd, fg: bool) -> WResult<()> {
+        self.get_listview_mut().run_proc_subshell(cmd, fg, true)?;
         Ok(())
     }
 
@@ -789,3 +803,10 @@ impl Acting for ListView<Vec<Process>> {
         Ok(())
     }
 }
+
+#[test]
+fn test_foreground() {
+    assert_eq!(foreground("vim!"), ("vim", true));
+    assert_eq!(foreground("echo done!!"), ("echo done!", true));
+    assert_eq!(foreground("ls -l"), ("ls -l", false));
+}
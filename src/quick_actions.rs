@@ -389,6 +389,18 @@ impl QuickAction {
 
         let cwd = files.get(0).ok_or(WError::NoneError)?.parent_as_file()?;
 
+        // Same as the substitution patterns of commands, see README. The
+        // prefix keeps them from overriding answers to the questions.
+        let tagged = crate::files::tagged_paths()?
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut answers = answers;
+        answers.push((OsString::from("WANDEX_FILE"), OsString::from(&files[0].path)));
+        answers.push((OsString::from("WANDEX_DIR"), OsString::from(&cwd.path)));
+        answers.push((OsString::from("WANDEX_TAGGED"), OsString::from(tagged)));
+
         let files: Vec<OsString> = files.iter().map(|f| OsString::from(&f.path)).collect();
 
         if self.sync {
@@ -405,9 +417,12 @@ impl QuickAction {
                 vars: Some(answers),
                 short_cmd: None,
                 cwd: cwd,
+                cwd_file: None,
+                prev_cwd: None,
                 cwd_files: None,
                 tab_files: None,
                 tab_paths: None,
+                input: None,
             };
 
             proc_view.lock().run_proc_raw(cmd)?;
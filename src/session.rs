@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use crate::config::Layout;
+use crate::fail::{ErrorLog, WError, WResult};
+use crate::files::{File, SortBy};
+use crate::fscache::TabSettings;
+use crate::paths::{escape_path, escape_value, unescape_path, unescape_value};
+
+// Sessions are saved under this name on quit
+lazy_static! {
+    static ref SESSION_NAME: RwLock<String> = RwLock::new(String::from("last"));
+}
+
+pub fn set_name(name: &str) -> WResult<()> {
+    *SESSION_NAME.write()? = name.to_string();
+    Ok(())
+}
+
+pub fn name() -> String {
+    SESSION_NAME
+        .read()
+        .map(|name| name.clone())
+        .unwrap_or_else(|_| String::from("last"))
+}
+
+pub fn list() -> WResult<Vec<String>> {
+    let sessions_path = crate::paths::sessions_path()?;
+
+    if !sessions_path.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut names = std::fs::read_dir(sessions_path)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect::<Vec<String>>();
+    names.sort();
+
+    Ok(names)
+}
+
+fn session_file(name: &str) -> WResult<PathBuf> {
+    // Don't let names escape the sessions directory
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        return Err(WError::Log(format!("Invalid session name: {}", name)));
+    }
+
+    let mut path = crate::paths::sessions_path()?;
+    path.push(name);
+    Ok(path)
+}
+
+#[derive(Debug, Clone)]
+pub struct TabSession {
+    pub cwd: File,
+    pub layout: Layout,
+    pub other_cwd: Option<File>,
+    pub name: Option<String>,
+    pub pinned: bool,
+    pub settings: HashMap<File, TabSettings>,
+}
+
+impl TabSession {
+    fn new(cwd: File) -> TabSession {
+        TabSession {
+            cwd,
+            layout: Layout::Miller,
+            other_cwd: None,
+            name: None,
+            pinned: false,
+            settings: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub active: usize,
+    pub tabs: Vec<TabSession>,
+}
+
+impl Session {
+    pub fn load(name: &str) -> WResult<Session> {
+        let content = std::fs::read_to_string(session_file(name)?)?;
+        let session = Session::parse(&content);
+
+        if session.tabs.is_empty() {
+            return Err(WError::Log(format!("Session {} has no tabs!", name)));
+        }
+
+        Ok(session)
+    }
+
+    pub fn save(&self, name: &str) -> WResult<()> {
+        let path = session_file(name)?;
+        std::fs::create_dir_all(crate::paths::sessions_path()?)?;
+        crate::paths::write_atomic(&path, self.to_string().as_bytes())
+    }
+
+    fn parse(content: &str) -> Session {
+        let mut session = Session {
+            active: 0,
+            tabs: vec![],
+        };
+        // Directory the following settings belong to
+        let mut dir: Option<File> = None;
+
+        for line in content.lines().filter(|line| !line.is_empty()) {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("");
+            let raw_value = parts.next().unwrap_or("");
+            let value = unescape_value(raw_value);
+            let value = value.as_str();
+
+            // Paths can have bytes that aren't UTF-8
+            let path_file = || File::new_from_path(&unescape_path(raw_value));
+            let on = value == "on";
+
+            match key {
+                "active" => session.active = value.parse().unwrap_or(0),
+                "tab" => {
+                    dir = None;
+                    match path_file() {
+                        Ok(cwd) => session.tabs.push(TabSession::new(cwd)),
+                        Err(err) => Err::<(), _>(err).log(),
+                    }
+                }
+                "layout" => {
+                    if let Some(tab) = session.tabs.last_mut() {
+                        tab.layout = value.parse().unwrap_or(Layout::Miller);
+                    }
+                }
+                "other" => {
+                    if let Some(tab) = session.tabs.last_mut() {
+                        tab.other_cwd = path_file().ok();
+                    }
+                }
+                "name" => {
+                    if let Some(tab) = session.tabs.last_mut() {
+                        tab.name = Some(value.to_string());
+                    }
+                }
+                "pinned" => {
+                    if let Some(tab) = session.tabs.last_mut() {
+                        tab.pinned = on;
+                    }
+                }
+                "dir" => dir = path_file().ok(),
+                _ => match (key, session.dir_settings(&dir)) {
+                    ("selection", Some(settings)) => settings.selection = path_file().ok(),
+                    ("selected", Some(settings)) => {
+                        path_file()
+                            .map(|file| settings.multi_selections.push(file))
+                            .ok();
+                    }
+                    ("sort", Some(settings)) => {
+                        settings.dir_settings.sort = match value {
+                            "size" => SortBy::Size,
+                            "mtime" => SortBy::MTime,
+                            _ => SortBy::Name,
+                        }
+                    }
+                    ("dirs_first", Some(settings)) => settings.dir_settings.dirs_first = on,
+                    ("reverse", Some(settings)) => settings.dir_settings.reverse = on,
+                    ("show_hidden", Some(settings)) => settings.dir_settings.show_hidden = on,
+                    ("filter", Some(settings)) => {
+                        settings.dir_settings.filter = Some(value.to_string())
+                    }
+                    ("filter_selected", Some(settings)) => {
+                        settings.dir_settings.filter_selected = on
+                    }
+                    _ => WError::config_error::<Session>(line.to_string()).log(),
+                }
+            }
+        }
+
+        session
+    }
+
+    fn dir_settings(&mut self, dir: &Option<File>) -> Option<&mut TabSettings> {
+        let dir = dir.as_ref()?;
+        let tab = self.tabs.last_mut()?;
+
+        Some(tab.settings
+                .entry(dir.clone())
+                .or_insert_with(TabSettings::new))
+    }
+}
+
+impl std::fmt::Display for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let on_off = |on| if on { "on" } else { "off" };
+        // Names with line breaks would start a new entry
+        let path = |file: &File| escape_path(&file.path);
+
+        writeln!(f, "active={}", self.active)?;
+
+        for tab in &self.tabs {
+            writeln!(f, "tab={}", path(&tab.cwd))?;
+            writeln!(f, "layout={}", tab.layout)?;
+            if let Some(other_cwd) = &tab.other_cwd {
+                writeln!(f, "other={}", path(other_cwd))?;
+            }
+            if let Some(name) = &tab.name {
+                writeln!(f, "name={}", escape_value(name))?;
+            }
+            writeln!(f, "pinned={}", on_off(tab.pinned))?;
+
+            for (dir, settings) in &tab.settings {
+                let dir_settings = &settings.dir_settings;
+
+                writeln!(f, "dir={}", path(dir))?;
+                if let Some(selection) = &settings.selection {
+                    writeln!(f, "selection={}", path(selection))?;
+                }
+                for selected in &settings.multi_selections {
+                    writeln!(f, "selected={}", path(selected))?;
+                }
+                writeln!(f, "sort={}", dir_settings.sort)?;
+                writeln!(f, "dirs_first={}", on_off(dir_settings.dirs_first))?;
+                writeln!(f, "reverse={}", on_off(dir_settings.reverse))?;
+                writeln!(f, "show_hidden={}", on_off(dir_settings.show_hidden))?;
+                if let Some(filter) = &dir_settings.filter {
+                    writeln!(f, "filter={}", escape_value(filter))?;
+                }
+                writeln!(f, "filter_selected={}", on_off(dir_settings.filter_selected))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_session_roundtrip() {
+    let file = |path: &str| File::new_from_path(&PathBuf::from(path)).unwrap();
+
+    let mut tab = TabSession::new(file("/tmp/new\nline"));
+    tab.layout = Layout::DualPane;
+    tab.other_cwd = Some(file("/tmp/other=dir"));
+    tab.name = Some(String::from("C:\\work"));
+    tab.pinned = true;
+
+    let mut settings = TabSettings::new();
+    settings.selection = Some(file("/tmp/new\nline/a b"));
+    settings.multi_selections = vec![file("/tmp/new\nline/x"), file("/tmp/new\nline/y")];
+    settings.dir_settings.sort = SortBy::MTime;
+    settings.dir_settings.reverse = true;
+    settings.dir_settings.show_hidden = false;
+    settings.dir_settings.filter = Some(String::from("*.rs"));
+    tab.settings.insert(file("/tmp/new\nline"), settings);
+
+    let session = Session { active: 1, tabs: vec![TabSession::new(file("/")), tab] };
+    let parsed = Session::parse(&session.to_string());
+
+    assert_eq!(parsed.active, 1);
+    assert_eq!(parsed.tabs.len(), 2);
+    assert_eq!(parsed.tabs[0].cwd.path, PathBuf::from("/"));
+    assert_eq!(parsed.tabs[0].layout, Layout::Miller);
+
+    let tab = &parsed.tabs[1];
+    assert_eq!(tab.cwd.path, PathBuf::from("/tmp/new\nline"));
+    assert!(tab.layout == Layout::DualPane && tab.pinned);
+    assert_eq!(tab.other_cwd.as_ref().unwrap().path, PathBuf::from("/tmp/other=dir"));
+    assert_eq!(tab.name.as_deref(), Some("C:\\work"));
+
+    let settings = tab.settings.values().next().unwrap();
+    assert_eq!(settings.selection.as_ref().unwrap().path, PathBuf::from("/tmp/new\nline/a b"));
+    assert_eq!(settings.multi_selections.len(), 2);
+    assert_eq!(settings.dir_settings.sort, SortBy::MTime);
+    assert!(settings.dir_settings.reverse && !settings.dir_settings.show_hidden);
+    assert_eq!(settings.dir_settings.filter.as_deref(), Some("*.rs"));
+}
@@ -0,0 +1,295 @@
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+// A word is collected in parts, so patterns can be expanded once it's done
+enum Part {
+    Text(Vec<u8>),
+    Pattern(Vec<OsString>),
+}
+
+// Single quotes keep everything as it is, even newlines. Only single
+// quotes themselves need to be closed, escaped and reopened.
+pub fn quote(text: &OsStr) -> OsString {
+    let mut quoted = vec![b'\''];
+
+    for &byte in text.as_bytes() {
+        match byte {
+            b'\'' => quoted.extend_from_slice(b"'\\''"),
+            byte => quoted.push(byte),
+        }
+    }
+
+    quoted.push(b'\'');
+    OsString::from_vec(quoted)
+}
+
+fn is_name_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+// Unquoted these end a word just like whitespace
+pub fn is_separator(byte: u8) -> bool {
+    b" \t\n|&;<>()".contains(&byte)
+}
+
+// Modifiers like ":t" that can follow an unbraced name, see README
+const MODIFIERS: &[u8] = b"atrhe";
+
+// Length of the modifiers at the start of text
+fn modifiers_len(text: &[u8]) -> usize {
+    text.chunks(2)
+        .take_while(|chunk| match chunk {
+            [b':', modifier] => MODIFIERS.contains(modifier),
+            _ => false,
+        })
+        .count() * 2
+}
+
+// Length and name of "$name" or "${name}" at the start of text,
+// including any modifiers
+fn pattern_at(text: &[u8]) -> Option<(usize, &str)> {
+    match text.get(1)? {
+        b'{' => {
+            let end = text.iter().position(|&byte| byte == b'}')?;
+            let name = std::str::from_utf8(&text[2..end]).ok()?;
+            Some((end + 1, name))
+        }
+        _ => {
+            let len = text[1..].iter().take_while(|&&byte| is_name_byte(byte)).count();
+            let len = len + modifiers_len(&text[1 + len..]);
+            let name = std::str::from_utf8(&text[1..=len]).ok()?;
+            Some((len + 1, name))
+        }
+    }
+}
+
+fn push_text(word: &mut Vec<Part>, text: &[u8]) {
+    match word.last_mut() {
+        Some(Part::Text(last)) => last.extend_from_slice(text),
+        _ => word.push(Part::Text(text.to_vec())),
+    }
+}
+
+// A word with a pattern standing for several files is repeated for each
+// of them, so "--out=$s" becomes "--out='a' --out='b'"
+fn expand_word(output: &mut Vec<u8>, word: Vec<Part>) {
+    let words = word.iter().fold(vec![vec![]], |words: Vec<Vec<u8>>, part| {
+        match part {
+            Part::Text(text) => words
+                .into_iter()
+                .map(|mut word| {
+                    word.extend_from_slice(text);
+                    word
+                })
+                .collect(),
+            Part::Pattern(values) => words
+                .iter()
+                .flat_map(|word| {
+                    values.iter().map(move |value| {
+                        let mut word = word.clone();
+                        word.extend_from_slice(quote(value).as_bytes());
+                        word
+                    })
+                })
+                .collect(),
+        }
+    });
+
+    output.extend(words.join(&b' '));
+}
+
+// Variables the command sets itself, like the "f" in "for f in *.jpg",
+// "f=..." or "read f". Those belong to the shell, not to the patterns.
+fn assigned_names(cmd: &[u8]) -> Vec<&[u8]> {
+    let is_name = |word: &[u8]| {
+        word.first().map(|byte| !byte.is_ascii_digit()).unwrap_or(false) &&
+            word.iter().all(|&byte| is_name_byte(byte))
+    };
+
+    // "read" takes names up to the end of its command
+    cmd.split(|byte| b";&|()\n".contains(byte))
+        .flat_map(|command| {
+            let words = command
+                .split(|&byte| is_separator(byte))
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>();
+
+            words
+                .iter()
+                .enumerate()
+                .flat_map(|(i, word)| {
+                    let rest = &words[i + 1..];
+                    let names: Vec<&[u8]> = match *word {
+                        b"for" | b"select" => rest.iter().take(1).cloned().collect(),
+                        b"read" => rest
+                            .iter()
+                            .skip_while(|arg| arg.starts_with(b"-"))
+                            .take_while(|arg| is_name(arg))
+                            .cloned()
+                            .collect(),
+                        word => match word.iter().position(|&byte| byte == b'=') {
+                            Some(pos) => vec![&word[..pos]],
+                            None => vec![],
+                        },
+                    };
+                    names
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|name| is_name(name))
+        .collect()
+}
+
+// Replaces the patterns lookup knows about with the quoted values,
+// anywhere in a word. Nothing is substituted in quotes, after a
+// backslash or for variables the command sets, so "$f" in a shell loop
+// is still the loop variable.
+pub fn substitute<F>(cmd: &OsStr, lookup: F) -> OsString
+where
+    F: Fn(&str) -> Option<Vec<OsString>>,
+{
+    let cmd = cmd.as_bytes();
+    let assigned = assigned_names(cmd);
+    let lookup = |name: &str| {
+        let base = name.split(':').next().unwrap_or(name);
+        match assigned.contains(&base.as_bytes()) {
+            true => None,
+            false => lookup(name),
+        }
+    };
+    let mut output = vec![];
+    let mut word = vec![];
+    let mut quote = None;
+    let mut pos = 0;
+
+    while pos < cmd.len() {
+        let byte = cmd[pos];
+
+        match (quote, byte) {
+            (None, byte) if is_separator(byte) => {
+                expand_word(&mut output, std::mem::take(&mut word));
+                output.push(byte);
+            }
+            (None, b'\\') | (Some(b'"'), b'\\') => {
+                let end = (pos + 2).min(cmd.len());
+                push_text(&mut word, &cmd[pos..end]);
+                pos = end;
+                continue;
+            }
+            (None, b'\'') | (None, b'"') => {
+                quote = Some(byte);
+                push_text(&mut word, &[byte]);
+            }
+            (Some(q), byte) if q == byte => {
+                quote = None;
+                push_text(&mut word, &[byte]);
+            }
+            (None, b'$') => {
+                let pattern = pattern_at(&cmd[pos..])
+                    .and_then(|(len, name)| Some((len, lookup(name)?)));
+
+                match pattern {
+                    Some((len, values)) => {
+                        word.push(Part::Pattern(values));
+                        pos += len;
+                        continue;
+                    }
+                    None => push_text(&mut word, &[byte]),
+                }
+            }
+            (_, byte) => push_text(&mut word, &[byte]),
+        }
+
+        pos += 1;
+    }
+
+    expand_word(&mut output, word);
+    OsString::from_vec(output)
+}
+
+// Whole name matches of *, ? and [a-z] or [!a-z] sets, like a shell glob
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    fn in_set(set: &[char], ch: char) -> bool {
+        match set {
+            [from, '-', to, rest @ ..] => (*from..=*to).contains(&ch) || in_set(rest, ch),
+            [first, rest @ ..] => *first == ch || in_set(rest, ch),
+            [] => false,
+        }
+    }
+
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match (pattern, name) {
+            ([], name) => name.is_empty(),
+            (['*', rest @ ..], name) => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+            (_, []) => false,
+            (['?', rest @ ..], [_, name @ ..]) => matches(rest, name),
+            (['[', set @ ..], [ch, name @ ..]) if set.contains(&']') => {
+                let end = set.iter().position(|&c| c == ']').unwrap();
+                let (negated, chars) = match &set[..end] {
+                    ['!', chars @ ..] | ['^', chars @ ..] => (true, chars),
+                    chars => (false, chars),
+                };
+
+                in_set(chars, *ch) != negated && matches(&set[end + 1..], name)
+            }
+            ([first, rest @ ..], [ch, name @ ..]) => first == ch && matches(rest, name),
+        }
+    }
+
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    matches(&pattern, &name)
+}
+
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+#[test]
+fn test_substitute() {
+    let files = vec![OsString::from("it's"), OsString::from("a\nb")];
+    let lookup = |name: &str| match name {
+        "s" => Some(files.clone()),
+        "d" => Some(vec![OsString::from("dir")]),
+        "d:t:r" => Some(vec![OsString::from("d")]),
+        _ => None,
+    };
+    let substituted = |cmd: &str| substitute(OsStr::new(cmd), lookup).into_string().unwrap();
+
+    assert_eq!(substituted("ls $s|wc"), "ls 'it'\\''s' 'a\nb'|wc");
+    assert_eq!(substituted("cp --out=$d/foo"), "cp --out='dir'/foo");
+    assert_eq!(substituted("echo \"x $d\" '$d' \\$d ${d}x $dx"),
+               "echo \"x $d\" '$d' \\$d 'dir'x $dx");
+    assert_eq!(substituted("mv $d:t:r.x ${d:t:r}:x"), "mv 'd'.x 'd':x");
+
+    // Shell variables with the same names as patterns are left alone
+    assert_eq!(substituted("for d in $s; do du $d; done; echo $d"),
+               "for d in 'it'\\''s' 'a\nb'; do du $d; done; echo $d");
+    assert_eq!(substituted("s=1 cmd $s;read -r d x<$s; ls $d:t"),
+               "s=1 cmd $s;read -r d x<$s; ls $d:t");
+    assert_eq!(substituted("cd $d && ls --s=$s"), "cd 'dir' && ls --s='it'\\''s' --s='a\nb'");
+}
+
+#[test]
+fn test_assigned_names() {
+    let names = |cmd: &str| {
+        assigned_names(cmd.as_bytes())
+            .into_iter()
+            .map(|name| String::from_utf8(name.to_vec()).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names("for f in *.jpg; do x=\"$f\"; done"), vec!["f", "x"]);
+    assert_eq!(names("while read -r a b; do :; done"), vec!["a", "b"]);
+    assert_eq!(names("ls --color=auto 1=2 $s"), Vec::<String>::new());
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("*.rs", "main.rs"));
+    assert!(!glob_match("*.rs", "main.rs.orig"));
+    assert!(glob_match("IMG_??.jp*g", "IMG_01.jpeg"));
+    assert!(glob_match("[a-c]*[!~]", "backup"));
+    assert!(!glob_match("[a-c]*[!~]", "backup~"));
+    assert!(glob_match("[x", "[x"));
+}
@@ -1,4 +1,5 @@
 use termion::event::Key;
+use unicode_width::UnicodeWidthStr;
 
 use crate::coordinates::Coordinates;
 use crate::fail::{ErrorLog, WError, WResult};
@@ -18,6 +19,10 @@ pub trait Tabbable {
     fn active_tab(&self) -> &Self::Tab;
     fn active_tab_mut(&mut self) -> &mut Self::Tab;
     fn on_key_sub(&mut self, key: Key) -> WResult<()>;
+    // Lets the active tab have a key before the tab bindings
+    fn captures_key(&self, _key: Key) -> bool {
+        false
+    }
     fn on_key(&mut self, key: Key) -> WResult<()> {
         self.on_key_sub(key)
     }
@@ -30,6 +35,27 @@ pub trait Tabbable {
     fn on_new(&mut self) -> WResult<()> {
         Ok(())
     }
+    fn save_session(&mut self) -> WResult<()> {
+        Ok(())
+    }
+    fn load_session(&mut self) -> WResult<()> {
+        Ok(())
+    }
+    fn list_sessions(&mut self) -> WResult<()> {
+        Ok(())
+    }
+    fn rename_tab(&mut self) -> WResult<()> {
+        Ok(())
+    }
+    fn duplicate_tab(&mut self) -> WResult<()> {
+        Ok(())
+    }
+    fn toggle_pin_tab(&mut self) -> WResult<()> {
+        Ok(())
+    }
+    fn command_line(&mut self) -> WResult<()> {
+        Ok(())
+    }
 }
 
 #[derive(PartialEq)]
@@ -114,6 +140,49 @@ where
         self.on_tab_switch().log();
     }
 
+    pub fn move_tab_(&mut self, forward: bool) {
+        let target = match forward {
+            true => self.active + 1,
+            false => self.active.wrapping_sub(1),
+        };
+
+        if target < self.widgets.len() {
+            self.widgets.swap(self.active, target);
+            self.active = target;
+        }
+    }
+
+    // Range of tabs shown in the tab bar, scrolled to the active tab
+    fn visible_tabs(&self, tabs: &[String], width: usize) -> (usize, usize) {
+        let tabs_width = |range: &[String]| range.iter().map(|tab| tab.width() + 1).sum::<usize>();
+
+        if tabs_width(tabs) <= width {
+            return (0, tabs.len());
+        }
+
+        // Leave room for the scroll markers
+        let width = width.saturating_sub(4);
+        let (mut start, mut end) = (self.active, self.active + 1);
+
+        loop {
+            let mut grown = false;
+
+            if end < tabs.len() && tabs_width(&tabs[start..end + 1]) <= width {
+                end += 1;
+                grown = true;
+            }
+            if start > 0 && tabs_width(&tabs[start - 1..end]) <= width {
+                start -= 1;
+                grown = true;
+            }
+            if !grown {
+                break;
+            }
+        }
+
+        (start, end)
+    }
+
     pub fn prev_tab_(&mut self) {
         if self.active == 0 {
             self.active = self.widgets.len() - 1;
@@ -149,35 +218,53 @@ where
     }
 
     fn render_header(&self) -> WResult<String> {
-        let xsize = self.get_coordinates()?.xsize();
+        let xsize = self.get_coordinates()?.xsize_u();
         let header = self.active_tab_().render_header()?;
-        let tab_names = self.get_tab_names();
+        let tabs = self
+            .get_tab_names()
+            .into_iter()
+            .enumerate()
+            .map(|(num, name)| format!("{}:{}", num, name.unwrap_or_default()))
+            .collect::<Vec<String>>();
+
+        // The tab bar may take what the header leaves, but at least a third
+        let free = xsize.saturating_sub(crate::term::string_len(&header) + 1);
+        let (start, end) = self.visible_tabs(&tabs, free.max(xsize / 3));
+
         let mut nums_length = 0;
-        let tabnums = (0..self.widgets.len())
+        let mut tabnums = (start..end)
             .map(|num| {
-                nums_length += format!("{}:{} ", num, tab_names[num].as_ref().unwrap()).len();
+                nums_length += tabs[num].width() + 1;
                 if num == self.active {
                     format!(
-                        " {}{}:{}{}{}",
+                        " {}{}{}{}",
                         crate::term::invert(),
-                        num,
-                        tab_names[num].as_ref().unwrap(),
+                        tabs[num],
                         crate::term::reset(),
                         crate::term::header_color()
                     )
                 } else {
-                    format!(" {}:{}", num, tab_names[num].as_ref().unwrap())
+                    format!(" {}", tabs[num])
                 }
             })
             .collect::<String>();
 
-        let nums_pos = xsize.saturating_sub(nums_length as u16);
+        if start > 0 {
+            tabnums.insert_str(0, " <");
+            nums_length += 2;
+        }
+        if end < tabs.len() {
+            tabnums.push_str(" >");
+            nums_length += 2;
+        }
+
+        let nums_pos = xsize.saturating_sub(nums_length);
 
         Ok(format!(
             "{}{}{}{}",
             header,
             crate::term::header_color(),
-            crate::term::goto_xy(nums_pos, 1),
+            crate::term::goto_xy(nums_pos as u16, 1),
             tabnums
         ))
     }
@@ -196,6 +283,10 @@ where
     }
 
     fn on_key(&mut self, key: Key) -> WResult<()> {
+        if self.captures_key(key) {
+            return Tabbable::on_key(self, key);
+        }
+
         match self.do_key(key) {
             Err(WError::WidgetUndefinedKeyError { .. }) => Tabbable::on_key(self, key)?,
             e @ _ => e?,
@@ -226,8 +317,38 @@ where
             CloseTab => self.close_tab()?,
             NextTab => self.next_tab()?,
             PrevTab => self.prev_tab()?,
+            SaveSession => self.save_session()?,
+            LoadSession => self.load_session()?,
+            ListSessions => self.list_sessions()?,
+            RenameTab => self.rename_tab()?,
+            MoveTabLeft => self.move_tab_(false),
+            MoveTabRight => self.move_tab_(true),
+            DuplicateTab => self.duplicate_tab()?,
+            TogglePinTab => self.toggle_pin_tab()?,
+            CommandLine => self.command_line()?,
         }
 
         Ok(())
     }
+
+    fn do_action_count(&mut self, action: &Self::Action, count: usize) -> WResult<()> {
+        use TabAction::*;
+
+        match action {
+            // Like vim's gt and gT, counts pick a tab going forward but
+            // move back relative to the current one. They start at 1,
+            // just like the F-keys for GotoTab
+            NextTab => self.goto_tab(count.saturating_sub(1)),
+            GotoTab(_) | SaveSession | LoadSession | ListSessions | RenameTab | TogglePinTab
+            | CommandLine => {
+                self.do_action(action)
+            }
+            _ => {
+                for _ in 0..count {
+                    self.do_action(action)?;
+                }
+                Ok(())
+            }
+        }
+    }
 }
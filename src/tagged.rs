@@ -0,0 +1,88 @@
+use crate::fail::{ErrorLog, WResult};
+use crate::files::{File, Files};
+use crate::keybind::{Bindings, KeyBinds, TaggedAction};
+use crate::listview::ListView;
+use crate::pathlist::{selected_or_current, FilePick, Outcome, PathList, PathListView};
+use crate::widget::Widget;
+
+// All tagged files from every directory, so they can be found again
+pub struct Tagged;
+
+pub type TaggedView = PathListView<Tagged>;
+
+impl Tagged {
+    fn untag(&self, files: Vec<File>, list: &ListView<Files>) -> WResult<Outcome<FilePick>> {
+        let paths = files.into_iter().map(|file| file.path).collect::<Vec<_>>();
+        crate::files::untag(&paths)?;
+
+        list.get_core()?
+            .show_status(&format!("Untagged {} files", paths.len()))
+            .log();
+
+        Ok(Outcome::Reload)
+    }
+}
+
+impl PathList for Tagged {
+    type Action = TaggedAction;
+    type Pick = FilePick;
+
+    const SELECTABLE: bool = true;
+
+    // Files from the same directory stay together
+    fn files(&self) -> WResult<Vec<File>> {
+        crate::files::tagged_paths()?
+            .iter()
+            .map(|path| {
+                let mut file = File::new_from_path(path)?;
+                file.name = match path.exists() {
+                    true => file.short_string(),
+                    false => format!("{} (missing)", file.short_string()),
+                };
+                Ok(file)
+            })
+            .collect()
+    }
+
+    fn header(&self, len: usize) -> String {
+        format!("Tagged: {} files", len)
+    }
+
+    fn footer_info(&self, file: &File) -> String {
+        let labels = file.get_tags().unwrap_or_default().into_iter().collect::<String>();
+        format!(" [{}]", labels)
+    }
+
+    fn bindings(keybinds: KeyBinds) -> Bindings<TaggedAction> {
+        keybinds.tagged
+    }
+
+    fn goto_action() -> TaggedAction {
+        TaggedAction::Goto
+    }
+
+    fn do_action(&mut self,
+                 action: &TaggedAction,
+                 list: &ListView<Files>) -> WResult<Outcome<FilePick>> {
+        use TaggedAction::*;
+
+        let outcome = match action {
+            Close => Outcome::Close,
+            Goto => Outcome::Pick(FilePick::Goto(list.selected_file().clone())),
+            Untag => self.untag(selected_or_current(list), list)?,
+            UntagMissing => {
+                let missing = list
+                    .content
+                    .iter_files()
+                    .filter(|file| !file.path.exists())
+                    .cloned()
+                    .collect();
+                self.untag(missing, list)?
+            }
+            QuickActions => Outcome::Pick(FilePick::QuickActions(selected_or_current(list))),
+            ExecCmd => Outcome::Pick(FilePick::ExecCmd(selected_or_current(list))),
+        };
+
+        Ok(outcome)
+    }
+}
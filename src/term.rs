@@ -368,6 +368,10 @@ pub fn color_light_green() -> String {
     format!("{}", termion::color::Fg(termion::color::LightGreen))
 }
 
+pub fn color_light_black() -> String {
+    format!("{}", termion::color::Fg(termion::color::LightBlack))
+}
+
 pub fn color_cyan() -> String {
     format!("{}", termion::color::Fg(termion::color::Cyan))
 }
@@ -395,6 +399,51 @@ pub fn from_lscolor(color: &lscolors::Color) -> String {
     }
 }
 
+// Parses colors as written in config files: a name like "red" or
+// "light_blue", an ANSI value like "208" or a hex value like "#ff8800"
+pub fn parse_color(color: &str) -> Option<String> {
+    use termion::color::*;
+
+    let color = color.trim().to_lowercase();
+
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+        let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+        let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+        return Some(format!("{}", Fg(Rgb(r, g, b))));
+    }
+
+    if let Ok(value) = color.parse::<u8>() {
+        return Some(format!("{}", Fg(AnsiValue(value))));
+    }
+
+    let color = match color.replace('-', "_").as_str() {
+        "black" => format!("{}", Fg(Black)),
+        "red" => format!("{}", Fg(Red)),
+        "green" => format!("{}", Fg(Green)),
+        "yellow" => format!("{}", Fg(Yellow)),
+        "blue" => format!("{}", Fg(Blue)),
+        "magenta" => format!("{}", Fg(Magenta)),
+        "cyan" => format!("{}", Fg(Cyan)),
+        "white" => format!("{}", Fg(White)),
+        "light_black" => format!("{}", Fg(LightBlack)),
+        "light_red" => format!("{}", Fg(LightRed)),
+        "light_green" => format!("{}", Fg(LightGreen)),
+        "light_yellow" => format!("{}", Fg(LightYellow)),
+        "light_blue" => format!("{}", Fg(LightBlue)),
+        "light_magenta" => format!("{}", Fg(LightMagenta)),
+        "light_cyan" => format!("{}", Fg(LightCyan)),
+        "light_white" => format!("{}", Fg(LightWhite)),
+        "orange" => color_orange(),
+        _ => return None,
+    };
+
+    Some(color)
+}
+
 // pub fn cursor_left(n: u16) -> String {
 //     format!("{}", termion::cursor::Left(n))
 // }
@@ -448,3 +497,7 @@ pub fn header_color() -> String {
 pub fn status_bg() -> String {
     format!("{}", termion::color::Bg(termion::color::LightBlue))
 }
+
+pub fn visual_bg() -> String {
+    format!("{}", termion::color::Bg(termion::color::AnsiValue::grayscale(5)))
+}
@@ -11,6 +11,7 @@ use crate::config::Config;
 use crate::coordinates::{Coordinates, Position, Size};
 use crate::dirty::{DirtyBit, Dirtyable};
 use crate::fail::{ErrorLog, WError, WResult};
+use crate::keybind::{keys_to_string, AnyKey, PendingKeys};
 use crate::minibuffer::MiniBuffer;
 use crate::signal_notify::{notify, Signal};
 use crate::term;
@@ -27,6 +28,8 @@ pub enum Events {
     RequestInput,
     Status(String),
     ConfigLoaded,
+    // A key sequence hasn't gone on since this key event
+    KeyTimeout(usize),
 }
 
 impl PartialEq for WidgetCore {
@@ -53,6 +56,8 @@ pub struct WidgetCore {
     pub event_sender: Arc<Mutex<Sender<Events>>>,
     event_receiver: Arc<Mutex<Option<Receiver<Events>>>>,
     pub status_bar_content: Arc<Mutex<Option<String>>>,
+    pub count: Arc<Mutex<Option<usize>>>,
+    pub pending_keys: Arc<Mutex<PendingKeys>>,
     term_size: (usize, usize),
     dirty: DirtyBit,
     pub config: Arc<RwLock<Async<Config>>>,
@@ -83,6 +88,8 @@ impl WidgetCore {
             event_sender: Arc::new(Mutex::new(sender)),
             event_receiver: Arc::new(Mutex::new(Some(receiver))),
             status_bar_content: status_bar_content,
+            count: Arc::new(Mutex::new(None)),
+            pending_keys: Arc::new(Mutex::new(PendingKeys::default())),
             term_size: (xsize, ysize),
             dirty: DirtyBit::new(),
             config: Arc::new(RwLock::new(config)),
@@ -133,6 +140,116 @@ impl WidgetCore {
         Ok(())
     }
 
+    // Adds a digit to the count typed before a key, like "5j"
+    pub fn push_count_digit(&self, digit: usize) -> WResult<()> {
+        let count = {
+            let mut count = self.count.lock();
+            let new_count = count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+            *count = Some(new_count);
+            new_count
+        };
+
+        // Not using show_status, the count doesn't belong in the log
+        *self.status_bar_content.lock() = Some(count.to_string());
+        self.draw_status()
+    }
+
+    // Waits for the rest of the sequence, or for the timeout to end it
+    pub fn push_pending_key(&self, key: Key, hints: Vec<(String, String)>) -> WResult<()> {
+        let (event, keys) = {
+            let mut pending = self.pending_keys.lock();
+            let event = pending.push(key, hints);
+            (event, pending.keys().iter().map(|&key| AnyKey::from(key)).collect::<Vec<_>>())
+        };
+
+        if let Some(timeout) = self.config().key_timeout {
+            let sender = self.get_sender();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(timeout));
+                sender.send(Events::KeyTimeout(event)).ok();
+            });
+        }
+
+        let count = self.get_count().map(|count| count.to_string()).unwrap_or_default();
+        *self.status_bar_content.lock() = Some(format!("{}{}", count, keys_to_string(&keys)));
+        self.draw_status()
+    }
+
+    // Blanks the rows of hints that aren't wanted anymore, before the
+    // widgets draw over them again
+    pub fn clear_key_hints(&self) -> WResult<()> {
+        let rows = {
+            let mut pending = self.pending_keys.lock();
+            match pending.hints().is_empty() {
+                true => std::mem::take(&mut pending.hint_rows),
+                false => 0,
+            }
+        };
+
+        // The status bar is on the last row
+        let ysize = term::ysize() as usize;
+        let clear = (ysize.saturating_sub(rows + 1)..ysize.saturating_sub(1))
+            .map(|row| format!("{}{}", term::goto_xy_u(0, row), termion::clear::CurrentLine))
+            .collect::<String>();
+        self.write_to_screen(&clear)
+    }
+
+    // Lists what can follow a pending key sequence in columns, right
+    // above the status bar
+    pub fn draw_key_hints(&self) -> WResult<()> {
+        let hints = self.pending_keys.lock().hints().to_vec();
+        if hints.is_empty() {
+            return Ok(());
+        }
+
+        let (xsize, ysize) = (term::xsize_u(), term::ysize() as usize);
+        let width = hints
+            .iter()
+            .map(|(keys, action)| term::string_len(keys) + action.len() + 4)
+            .max()
+            .unwrap_or(0);
+        let columns = (xsize / width.max(1)).max(1);
+        let rows = hints.len().div_ceil(columns).min(ysize / 2);
+
+        let output = hints
+            .chunks(columns)
+            .take(rows)
+            .enumerate()
+            .map(|(row, hints)| {
+                let line = hints
+                    .iter()
+                    .map(|(keys, action)| {
+                        let padding = width - term::string_len(keys) - action.len() - 2;
+                        format!("{}{}{} {}{}{}",
+                                term::highlight_color(),
+                                keys,
+                                term::normal_color(),
+                                term::color_light_black(),
+                                action,
+                                " ".repeat(padding))
+                    })
+                    .collect::<String>();
+
+                format!("{}{}{}{}",
+                        term::goto_xy_u(0, ysize - rows + row - 1),
+                        termion::clear::CurrentLine,
+                        line,
+                        term::reset())
+            })
+            .collect::<String>();
+
+        self.pending_keys.lock().hint_rows = rows;
+        self.write_to_screen(&output)
+    }
+
+    pub fn get_count(&self) -> Option<usize> {
+        *self.count.lock()
+    }
+
+    pub fn take_count(&self) -> Option<usize> {
+        self.count.lock().take()
+    }
+
     pub fn minibuffer_clear(&self) -> WResult<()> {
         self.minibuffer
             .lock()
@@ -207,6 +324,14 @@ impl WidgetCore {
         let conf = self.config.read().get()?.clone();
         Ok(conf)
     }
+
+    // Only lasts until the next start, the config file isn't touched
+    pub fn set_config(&self, name: &str, value: &str) -> WResult<()> {
+        let mut config = self.config.write();
+        let config = config.get_mut()?;
+        *config = config.with_setting(name, value)?;
+        Ok(())
+    }
 }
 
 impl Dirtyable for WidgetCore {
@@ -254,6 +379,49 @@ pub trait Widget {
         Ok(())
     }
 
+    // What the event loops call for input, a key that doesn't go on with
+    // the pending sequence gets the sequence's keys their own meaning back
+    fn on_input(&mut self, event: Event) -> WResult<()> {
+        self.get_core()?.pending_keys.lock().next_event();
+
+        match self.on_event(event.clone()) {
+            Err(WError::WidgetUndefinedKeyError { .. })
+                if self.get_core()?.pending_keys.lock().went_nowhere() =>
+            {
+                self.finish_key_sequence().log();
+                self.on_event(event)
+            }
+            result => result,
+        }
+    }
+
+    // Replays the keys, each time running the longest bound sequence at the
+    // start of what's left, or else the first key alone
+    fn finish_key_sequence(&mut self) -> WResult<()> {
+        let mut keys = self.get_core()?.pending_keys.lock().take_keys();
+        self.get_core()?.clear_status().log();
+
+        let mut result = Ok(());
+        while !keys.is_empty() && result.is_ok() {
+            for len in (1..=keys.len()).rev() {
+                self.get_core()?.pending_keys.lock().finish(&keys[..len - 1]);
+
+                match self.on_key(keys[len - 1]) {
+                    Err(WError::WidgetUndefinedKeyError { .. }) if len > 1 => continue,
+                    // Not bound at all, nothing to do for this one
+                    Err(WError::WidgetUndefinedKeyError { .. }) => {}
+                    key_result => result = key_result,
+                }
+
+                keys.drain(..len);
+                break;
+            }
+        }
+
+        self.get_core()?.pending_keys.lock().clear();
+        result
+    }
+
     fn on_event(&mut self, event: Event) -> WResult<()> {
         self.get_core()?.clear_status().log();
         match event {
@@ -374,16 +542,12 @@ pub trait Widget {
         for event in rx_event.iter() {
             match event {
                 Events::InputEvent(input) => {
-                    match self.on_event(input) {
-                        err @ Err(WError::PopupFinished)
-                        | err @ Err(WError::Quit)
-                        | err @ Err(WError::WidgetResizedError) => err?,
-                        event @ Err(WError::MiniBufferEvent(_)) => event?,
-                        err @ Err(_) => err.log(),
-                        Ok(_) => {}
-                    }
+                    popup_result(self.on_input(input))?;
                     self.get_core()?.get_sender().send(Events::RequestInput)?;
                 }
+                Events::KeyTimeout(event) if self.get_core()?.pending_keys.lock().timed_out(event) => {
+                    popup_result(self.finish_key_sequence())?;
+                }
                 Events::WidgetReady => {
                     return Err(WError::RefreshParent);
                 }
@@ -403,9 +567,11 @@ pub trait Widget {
                 }
                 _ => {}
             }
+            self.get_core()?.clear_key_hints().log();
             self.refresh().log();
             self.draw().log();
             self.after_draw().log();
+            self.get_core()?.draw_key_hints().log();
         }
         Ok(())
     }
@@ -489,14 +655,16 @@ pub trait Widget {
         for event in rx_internal_event.iter() {
             match event {
                 Events::InputEvent(event) => {
-                    match self.on_event(event) {
-                        Err(WError::Quit) => {
-                            WError::quit()?;
-                        }
-                        _ => {}
+                    if let Err(WError::Quit) = self.on_input(event) {
+                        WError::quit()?;
                     }
                     self.get_core()?.get_sender().send(Events::RequestInput)?;
                 }
+                Events::KeyTimeout(event) if self.get_core()?.pending_keys.lock().timed_out(event) => {
+                    if let Err(WError::Quit) = self.finish_key_sequence() {
+                        WError::quit()?;
+                    }
+                }
                 Events::Status(status) => {
                     self.get_core()?.show_status(&status).log();
                 }
@@ -513,8 +681,10 @@ pub trait Widget {
             if self.get_core()?.screen()?.is_resized()? {
                 self.get_core()?.screen()?.take_size().ok();
             }
+            self.get_core()?.clear_key_hints().log();
             self.refresh().ok();
             self.draw().ok();
+            self.get_core()?.draw_key_hints().log();
         }
         Ok(())
     }
@@ -530,6 +700,21 @@ pub trait Widget {
     }
 }
 
+// Popups end on some errors, the rest only get logged
+fn popup_result(result: WResult<()>) -> WResult<()> {
+    match result {
+        err @ Err(WError::PopupFinished)
+        | err @ Err(WError::Quit)
+        | err @ Err(WError::WidgetResizedError) => err,
+        event @ Err(WError::MiniBufferEvent(_)) => event,
+        err @ Err(_) => {
+            err.log();
+            Ok(())
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
 fn dispatch_events(tx_internal: Sender<Events>, rx_global: Receiver<Events>, screen: Screen) {
     let (tx_event, rx_event) = channel();
     let (tx_input_req, rx_input_req) = channel();